@@ -0,0 +1,65 @@
+//! Compare the sine implementations, alone and in the band-limited saw at full
+//! harmonics. Run with `cargo bench`.
+
+use diysynth::{
+    oscillator::advance_saw_osc_bl_with,
+    sine::{PolySine, Sine, StdSine, TableSine},
+};
+use std::{hint::black_box, time::Instant};
+
+const SAMPLE_RATE: f32 = 44100.0;
+const NUM_SAMPLES: usize = 441_000;
+
+fn bench_sine<S: Sine>(name: &str) -> f64 {
+    let start = Instant::now();
+    let mut sum = 0.0;
+    for i in 0..NUM_SAMPLES {
+        sum += S::sin(black_box(i as f32 * 0.01));
+    }
+    black_box(sum);
+
+    let ns = start.elapsed().as_nanos() as f64 / NUM_SAMPLES as f64;
+    println!("sin        {:<10} {:>8.2} ns/call", name, ns);
+    ns
+}
+
+fn bench_saw<S: Sine>(name: &str) -> f64 {
+    // 220 Hz has 100 harmonics under the Nyquist frequency
+    let freq = 220.0;
+    let harmonics = (SAMPLE_RATE * 0.5 / freq) as i8;
+    let mut phase = 0.0;
+
+    let start = Instant::now();
+    let mut sum = 0.0;
+    for _ in 0..NUM_SAMPLES {
+        sum += advance_saw_osc_bl_with::<S>(
+            &mut phase,
+            black_box(freq),
+            SAMPLE_RATE,
+            black_box(harmonics),
+        );
+    }
+    black_box(sum);
+
+    let ns = start.elapsed().as_nanos() as f64 / NUM_SAMPLES as f64;
+    println!("saw bl     {:<10} {:>8.2} ns/sample", name, ns);
+    ns
+}
+
+fn main() {
+    let std_ns = bench_sine::<StdSine>("std");
+    for (name, ns) in [
+        ("table", bench_sine::<TableSine>("table")),
+        ("poly", bench_sine::<PolySine>("poly")),
+    ] {
+        println!("           {:<10} {:>8.2}x std", name, std_ns / ns);
+    }
+
+    let std_ns = bench_saw::<StdSine>("std");
+    for (name, ns) in [
+        ("table", bench_saw::<TableSine>("table")),
+        ("poly", bench_saw::<PolySine>("poly")),
+    ] {
+        println!("           {:<10} {:>8.2}x std", name, std_ns / ns);
+    }
+}
@@ -0,0 +1,49 @@
+//! Arpeggio played by a saw wave shaped with an ADSR envelope
+use diysynth::{
+    envelope::{Adsr, Curve},
+    note_frequency,
+    oscillator::{Oscillator, SawOscPolyBlep},
+};
+
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    let sample_rate = 44100;
+    let seconds = 4;
+    let channels = 1;
+    let samples = sample_rate * channels * seconds;
+    let mut data = Vec::with_capacity(samples);
+
+    // Play an eighth note every quarter note so each release can be heard
+    let notes = [3.0, 7.0, 10.0, 15.0];
+    let mut osc = SawOscPolyBlep::new(note_frequency(3.0, notes[0]), sample_rate as f32);
+    let mut env = Adsr::new(sample_rate as f32, 0.01, 0.1, 0.6, 0.2);
+    env.set_curve(Curve::Exponential);
+    for i in 0..samples {
+        let quarter_note = i * 4 / sample_rate;
+        if i % (sample_rate / 4) == 0 {
+            osc.set_frequency(note_frequency(3.0, notes[quarter_note % notes.len()]));
+            env.gate_on();
+        } else if i % (sample_rate / 4) == sample_rate / 8 {
+            env.gate_off();
+        }
+        data.push(osc.next_sample() * env.next() * 0.5);
+    }
+
+    // Write file out
+    let mut w = std::io::BufWriter::new(std::fs::File::create("adsr.wav")?);
+    diysynth::MinimalWaveHeader::write_wave_file::<_, i16>(
+        &mut w,
+        &data,
+        samples as u32,
+        channels as u16,
+        sample_rate as u32,
+    )?;
+
+    Ok(())
+}
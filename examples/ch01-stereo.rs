@@ -41,5 +41,7 @@ fn try_main() -> Result<(), Box<dyn std::error::Error>> {
         channels as u16,
         sample_rate as u32,
         data_size as u16 * 8,
-    )
+    )?;
+
+    Ok(())
 }
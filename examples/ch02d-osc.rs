@@ -76,5 +76,7 @@ fn try_main() -> Result<(), Box<dyn std::error::Error>> {
         samples as u32,
         channels as u16,
         sample_rate as u32,
-    )
+    )?;
+
+    Ok(())
 }
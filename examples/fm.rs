@@ -0,0 +1,50 @@
+//! Electric piano, bell, and bass notes played by FM voices
+use diysynth::{
+    fm::{FmVoice, Patch},
+    note_frequency,
+    oscillator::Oscillator,
+};
+
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    let sample_rate = 44100;
+    let seconds = 6;
+    let channels = 1;
+    let samples = sample_rate * channels * seconds;
+    let mut data = Vec::with_capacity(samples);
+
+    // Each patch gets two seconds, holding its note for one second
+    let patches = [
+        (Patch::electric_piano(), note_frequency(4.0, 3.0)),
+        (Patch::bell(), note_frequency(5.0, 3.0)),
+        (Patch::bass(), note_frequency(2.0, 3.0)),
+    ];
+    for (patch, freq) in patches.iter() {
+        let mut voice = FmVoice::new(patch, *freq, sample_rate as f32);
+        voice.note_on(*freq);
+        for i in 0..sample_rate * 2 {
+            if i == sample_rate {
+                voice.note_off();
+            }
+            data.push(voice.next_sample() * 0.8);
+        }
+    }
+
+    // Write file out
+    let mut w = std::io::BufWriter::new(std::fs::File::create("fm.wav")?);
+    diysynth::MinimalWaveHeader::write_wave_file::<_, i16>(
+        &mut w,
+        &data,
+        samples as u32,
+        channels as u16,
+        sample_rate as u32,
+    )?;
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
 //! Example from the README, generates a 4 note chord made up of sine waves
-use diysynth::{note_frequency, oscillator::advance_sine_osc};
+use diysynth::{
+    note_frequency,
+    oscillator::{Oscillator, SineOsc},
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let sample_rate = 44100;
@@ -9,21 +12,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut data = Vec::with_capacity(samples);
 
     // Initialize 4 oscillators with their respective notes
-    let mut phase0 = 0.0;
-    let mut phase1 = 0.0;
-    let mut phase2 = 0.0;
-    let mut phase3 = 0.0;
-    let freq0 = note_frequency(3.0, 3.0);
-    let freq1 = note_frequency(3.0, 7.0);
-    let freq2 = note_frequency(4.0, 0.0);
-    let freq3 = note_frequency(4.0, 5.0);
+    let mut oscs = [
+        SineOsc::new(note_frequency(3.0, 3.0), sample_rate as f32),
+        SineOsc::new(note_frequency(3.0, 7.0), sample_rate as f32),
+        SineOsc::new(note_frequency(4.0, 0.0), sample_rate as f32),
+        SineOsc::new(note_frequency(4.0, 5.0), sample_rate as f32),
+    ];
     for _ in 0..samples {
-        data.push(
-            advance_sine_osc(&mut phase0, freq0, sample_rate as f32)
-                + advance_sine_osc(&mut phase1, freq1, sample_rate as f32)
-                + advance_sine_osc(&mut phase2, freq2, sample_rate as f32)
-                + advance_sine_osc(&mut phase3, freq3, sample_rate as f32),
-        );
+        data.push(oscs.iter_mut().map(|osc| osc.next_sample()).sum::<f32>());
     }
 
     // Normalize the data by the largest absolute value it contains
@@ -43,5 +39,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         samples as u32,
         channels as u16,
         sample_rate as u32,
-    )
+    )?;
+
+    Ok(())
 }
@@ -0,0 +1,190 @@
+use crate::oscillator::{advance_sine_osc_pm, Oscillator};
+
+/// Single sine partial of an additive oscillator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Partial {
+    /// Frequency as a multiple of the fundamental, which doesn't need to be a
+    /// whole number.
+    pub ratio: f32,
+    /// Linear amplitude.
+    pub amplitude: f32,
+    /// Phase offset in radians.
+    pub phase: f32,
+}
+
+impl Partial {
+    /// Create a new partial.
+    pub fn new(ratio: f32, amplitude: f32, phase: f32) -> Self {
+        Self {
+            ratio,
+            amplitude,
+            phase,
+        }
+    }
+}
+
+/// Partials of the harmonic series up to `count`, with the amplitude of each
+/// harmonic given by `amplitude` from its harmonic number starting at 1.
+pub fn harmonic_series(count: usize, amplitude: impl Fn(usize) -> f32) -> Vec<Partial> {
+    (1..=count)
+        .map(|n| Partial::new(n as f32, amplitude(n), 0.0))
+        .collect()
+}
+
+/// Inharmonic partials of a struck bell.
+pub fn bell_partials() -> Vec<Partial> {
+    vec![
+        Partial::new(0.5, 0.5, 0.0),
+        Partial::new(1.0, 1.0, 0.0),
+        Partial::new(1.19, 0.6, 0.0),
+        Partial::new(1.56, 0.4, 0.0),
+        Partial::new(2.0, 0.5, 0.0),
+        Partial::new(2.51, 0.3, 0.0),
+        Partial::new(2.66, 0.2, 0.0),
+        Partial::new(3.01, 0.15, 0.0),
+        Partial::new(4.1, 0.1, 0.0),
+    ]
+}
+
+/// Additive oscillator summing a set of sine partials, optionally morphing
+/// towards a second set. Partials at or above the Nyquist frequency are
+/// skipped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdditiveOsc {
+    partials: Vec<Partial>,
+    target: Vec<Partial>,
+    // One phase per partial of the larger set
+    phases: Vec<f32>,
+    freq: f32,
+    sample_rate: f32,
+    morph: f32,
+    morph_step: f32,
+    morph_end: f32,
+    morph_samples: u32,
+}
+
+impl AdditiveOsc {
+    /// Create a new oscillator starting at phase 0.
+    pub fn new(partials: Vec<Partial>, freq: f32, sample_rate: f32) -> Self {
+        let mut osc = Self {
+            partials,
+            target: Vec::new(),
+            phases: Vec::new(),
+            freq,
+            sample_rate,
+            morph: 0.0,
+            morph_step: 0.0,
+            morph_end: 0.0,
+            morph_samples: 0,
+        };
+        osc.resize_phases();
+        osc
+    }
+
+    /// Replace the partials, keeping the phase of partials which remain.
+    pub fn set_partials(&mut self, partials: Vec<Partial>) {
+        self.partials = partials;
+        self.resize_phases();
+    }
+
+    /// Set the partials to morph towards. Partials missing from the shorter
+    /// set fade in or out at the ratio of the other set.
+    pub fn set_morph_target(&mut self, target: Vec<Partial>) {
+        self.target = target;
+        self.resize_phases();
+    }
+
+    /// Amount of the morph from 0.0 for the partials to 1.0 for the target.
+    pub fn morph(&self) -> f32 {
+        self.morph
+    }
+
+    /// Set the amount of the morph immediately, stopping any morph in
+    /// progress.
+    pub fn set_morph(&mut self, morph: f32) {
+        self.morph = morph;
+        self.morph_samples = 0;
+    }
+
+    /// Linearly move the amount of the morph to `morph` over `seconds`.
+    pub fn morph_to(&mut self, morph: f32, seconds: f32) {
+        let samples = (seconds * self.sample_rate) as u32;
+        if samples == 0 {
+            self.set_morph(morph);
+        } else {
+            self.morph_step = (morph - self.morph) / samples as f32;
+            self.morph_end = morph;
+            self.morph_samples = samples;
+        }
+    }
+
+    /// Interpolated partial at `index` for the current amount of the morph.
+    fn partial(&self, index: usize) -> Partial {
+        match (self.partials.get(index), self.target.get(index)) {
+            (Some(&a), Some(b)) => Partial::new(
+                a.ratio + (b.ratio - a.ratio) * self.morph,
+                a.amplitude + (b.amplitude - a.amplitude) * self.morph,
+                a.phase + (b.phase - a.phase) * self.morph,
+            ),
+            (Some(&a), None) => Partial::new(a.ratio, a.amplitude * (1.0 - self.morph), a.phase),
+            (None, Some(&b)) => Partial::new(b.ratio, b.amplitude * self.morph, b.phase),
+            (None, None) => Partial::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    fn resize_phases(&mut self) {
+        let len = self.partials.len().max(self.target.len());
+        self.phases.resize(len, 0.0);
+    }
+}
+
+impl Oscillator for AdditiveOsc {
+    fn next_sample(&mut self) -> f32 {
+        if self.morph_samples > 0 {
+            self.morph_samples -= 1;
+            // Land exactly on the end of the morph despite rounding
+            self.morph = if self.morph_samples == 0 {
+                self.morph_end
+            } else {
+                self.morph + self.morph_step
+            };
+        }
+
+        let nyquist = self.sample_rate * 0.5;
+        let mut ret = 0.0;
+        for i in 0..self.phases.len() {
+            let partial = self.partial(i);
+            let freq = self.freq * partial.ratio;
+            let out =
+                advance_sine_osc_pm(&mut self.phases[i], freq, self.sample_rate, partial.phase);
+            // Keep advancing partials above Nyquist so they come back in phase
+            if freq.abs() < nyquist {
+                ret += out * partial.amplitude;
+            }
+        }
+        ret
+    }
+
+    fn reset(&mut self) {
+        for phase in self.phases.iter_mut() {
+            *phase = 0.0;
+        }
+    }
+
+    fn frequency(&self) -> f32 {
+        self.freq
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq;
+    }
+
+    /// Set the phase of the fundamental, moving each partial to where it would
+    /// be at the same point in time.
+    fn set_phase(&mut self, phase: f32) {
+        for i in 0..self.phases.len() {
+            let cycles = phase * self.partial(i).ratio;
+            self.phases[i] = (cycles - cycles.floor()) * core::f32::consts::TAU;
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    delay::{DelayInterpolation, DelayLine},
+    effect::Effect,
+    lfo::{sweep_lfo, Lfo, LfoRate, LfoShape},
+    oscillator::Oscillator,
+    FloatComponent,
+};
+
+/// Longest base delay or depth of the chorus and flanger in seconds.
+const MAX_DELAY: f32 = 0.05;
+
+/// Stereo chorus of several voices reading a delay line at times swept by
+/// their own LFOs, spread evenly through the cycle. The right channel LFOs run
+/// a quarter cycle ahead of the left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chorus {
+    lines: [DelayLine; 2],
+    lfos: Vec<[Lfo; 2]>,
+    sample_rate: f32,
+    delay: f32,
+    depth: f32,
+    mix: f32,
+}
+
+impl Chorus {
+    /// Create a new chorus with a sine LFO at 0.8 Hz, a delay of 15 ms swept
+    /// by a depth of 3 ms, and a mix of 0.5.
+    ///
+    /// Panics if `num_voices` is 0.
+    pub fn new(num_voices: usize, sample_rate: f32) -> Self {
+        assert!(num_voices > 0, "chorus needs at least one voice");
+        let max_delay = (2.0 * MAX_DELAY * sample_rate).ceil() as usize;
+        let line = DelayLine::new(max_delay, DelayInterpolation::Cubic);
+        let rate = LfoRate::Hertz(0.8);
+        let lfos = (0..num_voices)
+            .map(|i| {
+                let offset = i as f32 / num_voices as f32;
+                [
+                    sweep_lfo(LfoShape::Sine, rate, offset, sample_rate),
+                    sweep_lfo(LfoShape::Sine, rate, offset + 0.25, sample_rate),
+                ]
+            })
+            .collect();
+
+        let mut chorus = Self {
+            lines: [line.clone(), line],
+            lfos,
+            sample_rate,
+            delay: 0.0,
+            depth: 0.0,
+            mix: 0.5,
+        };
+        chorus.set_delay(0.015);
+        chorus.set_depth(0.003);
+        chorus
+    }
+
+    /// Set the rate of the LFOs.
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// Set the shape of the LFOs, usually sine or triangle.
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.set_shape(shape);
+        }
+    }
+
+    /// Set the shortest delay in seconds, up to 50 ms.
+    pub fn set_delay(&mut self, seconds: f32) {
+        self.delay = f32::clamp_component(seconds, 0.0, MAX_DELAY) * self.sample_rate;
+    }
+
+    /// Set how far in seconds the LFOs sweep the delay, up to 50 ms.
+    pub fn set_depth(&mut self, seconds: f32) {
+        self.depth = f32::clamp_component(seconds, 0.0, MAX_DELAY) * self.sample_rate;
+    }
+
+    /// Set the balance between the dry input at 0.0 and the voices at 1.0.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+}
+
+impl Effect for Chorus {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let mut out = [0.0; 2];
+        for (channel, out) in out.iter_mut().enumerate() {
+            let line = &mut self.lines[channel];
+            line.write(frame[channel]);
+
+            let mut wet = 0.0;
+            for lfos in self.lfos.iter_mut() {
+                wet += line.read(self.delay + self.depth * lfos[channel].next_sample());
+            }
+            wet /= self.lfos.len() as f32;
+
+            *out = frame[channel] * (1.0 - self.mix) + wet * self.mix;
+        }
+        out
+    }
+
+    fn reset(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.reset();
+        }
+        for lfo in self.lfos.iter_mut().flatten() {
+            lfo.reset();
+        }
+    }
+}
+
+/// Stereo flanger reading a delay line at a time swept by an LFO, with the
+/// output fed back into the line. In through-zero mode the dry signal is
+/// delayed to the middle of the sweep, so the sweep passes through zero delay
+/// relative to the dry signal. The right channel LFO runs a quarter cycle
+/// ahead of the left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flanger {
+    lines: [DelayLine; 2],
+    // Input without feedback, read as the dry signal in through-zero mode
+    dry_lines: [DelayLine; 2],
+    lfos: [Lfo; 2],
+    sample_rate: f32,
+    delay: f32,
+    depth: f32,
+    feedback: f32,
+    mix: f32,
+    through_zero: bool,
+}
+
+impl Flanger {
+    /// Create a new flanger with a triangle LFO at 0.25 Hz, a delay of 1 ms
+    /// swept by a depth of 3 ms, a feedback of 0.5, and a mix of 0.5.
+    pub fn new(sample_rate: f32) -> Self {
+        let max_delay = (3.0 * MAX_DELAY * sample_rate).ceil() as usize;
+        let line = DelayLine::new(max_delay, DelayInterpolation::Cubic);
+        let rate = LfoRate::Hertz(0.25);
+
+        let mut flanger = Self {
+            lines: [line.clone(), line.clone()],
+            dry_lines: [line.clone(), line],
+            lfos: [
+                sweep_lfo(LfoShape::Triangle, rate, 0.0, sample_rate),
+                sweep_lfo(LfoShape::Triangle, rate, 0.25, sample_rate),
+            ],
+            sample_rate,
+            delay: 0.0,
+            depth: 0.0,
+            feedback: 0.5,
+            mix: 0.5,
+            through_zero: false,
+        };
+        flanger.set_delay(0.001);
+        flanger.set_depth(0.003);
+        flanger
+    }
+
+    /// Set the rate of the LFOs.
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// Set the shape of the LFOs, usually sine or triangle.
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_shape(shape);
+        }
+    }
+
+    /// Set the shortest delay in seconds, up to 50 ms. In through-zero mode
+    /// this is the extra delay of the dry signal beyond the depth.
+    pub fn set_delay(&mut self, seconds: f32) {
+        self.delay = f32::clamp_component(seconds, 0.0, MAX_DELAY) * self.sample_rate;
+    }
+
+    /// Set how far in seconds the LFOs sweep the delay, up to 50 ms.
+    pub fn set_depth(&mut self, seconds: f32) {
+        self.depth = f32::clamp_component(seconds, 0.0, MAX_DELAY) * self.sample_rate;
+    }
+
+    /// Set the amount of the output fed back into the delay, from -0.99 to
+    /// 0.99. Negative feedback emphasizes odd harmonics of the sweep.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = f32::clamp_component(feedback, -0.99, 0.99);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the delayed signal at
+    /// 1.0, where 0.5 gives the deepest notches.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+
+    /// Set whether the dry signal is delayed so the sweep passes through zero.
+    pub fn set_through_zero(&mut self, through_zero: bool) {
+        self.through_zero = through_zero;
+    }
+}
+
+impl Effect for Flanger {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let mut out = [0.0; 2];
+        for (channel, out) in out.iter_mut().enumerate() {
+            let line = &mut self.lines[channel];
+            let dry_line = &mut self.dry_lines[channel];
+            let sweep = self.lfos[channel].next_sample();
+
+            let (dry, wet) = if self.through_zero {
+                // Sweep the wet signal from a depth before to a depth after
+                // the dry signal
+                let center = self.delay + self.depth;
+                let wet = line.read(center + self.depth * (sweep * 2.0 - 1.0));
+                (dry_line.read(center), wet)
+            } else {
+                (frame[channel], line.read(self.delay + self.depth * sweep))
+            };
+            line.write(frame[channel] + wet * self.feedback);
+            dry_line.write(frame[channel]);
+
+            *out = dry * (1.0 - self.mix) + wet * self.mix;
+        }
+        out
+    }
+
+    fn reset(&mut self) {
+        for line in self.lines.iter_mut().chain(self.dry_lines.iter_mut()) {
+            line.reset();
+        }
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{
+    effect::Effect,
+    filter::{Biquad, BiquadMode, Filter},
+    wavetable::hermite,
+    FloatComponent,
+};
+
+/// Interpolation between samples when reading a fractional delay.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DelayInterpolation {
+    /// Linear interpolation between the two nearest samples, which slightly
+    /// dulls the high frequencies at fractional delays.
+    Linear,
+    /// First order allpass interpolation, which keeps a flat frequency
+    /// response but smears fast changes in the delay time.
+    AllPass,
+    /// Cubic Hermite interpolation of the four nearest samples.
+    Cubic,
+}
+
+/// Circular buffer which reads the input back after a fractional number of
+/// samples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    mask: usize,
+    write_pos: usize,
+    max_delay: usize,
+    interpolation: DelayInterpolation,
+    allpass_state: f32,
+}
+
+impl DelayLine {
+    /// Create a silent delay line holding up to `max_delay` samples.
+    pub fn new(max_delay: usize, interpolation: DelayInterpolation) -> Self {
+        // Room for the samples on either side of the longest delay
+        let size = (max_delay + 3).next_power_of_two();
+        Self {
+            buffer: vec![0.0; size],
+            mask: size - 1,
+            write_pos: 0,
+            max_delay,
+            interpolation,
+            allpass_state: 0.0,
+        }
+    }
+
+    /// Longest delay in samples.
+    pub fn max_delay(&self) -> usize {
+        self.max_delay
+    }
+
+    /// Set the interpolation used when reading.
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Push the next input sample into the line.
+    pub fn write(&mut self, sample: f32) {
+        self.buffer[self.write_pos] = sample;
+        self.write_pos = (self.write_pos + 1) & self.mask;
+    }
+
+    /// Read the input from `delay` samples ago, where 1.0 is the most recently
+    /// written sample, limited to between 1.0 and the maximum delay. Allpass
+    /// interpolation keeps state between reads, so only read once per sample.
+    pub fn read(&mut self, delay: f32) -> f32 {
+        let delay = f32::clamp_component(delay, 1.0, self.max_delay as f32);
+        let mut whole = delay as usize;
+        let mut frac = delay - whole as f32;
+
+        match self.interpolation {
+            DelayInterpolation::Linear => {
+                let (y_0, y_1) = (self.tap(whole), self.tap(whole + 1));
+                y_0 + (y_1 - y_0) * frac
+            }
+            DelayInterpolation::AllPass => {
+                // Keep the fractional part between 0.618 and 1.618 when
+                // possible, since the allpass pole approaches -1 near 0.0
+                if frac < 0.618 && whole > 1 {
+                    whole -= 1;
+                    frac += 1.0;
+                }
+                let a = (1.0 - frac) / (1.0 + frac);
+                self.allpass_state =
+                    a * self.tap(whole) + self.tap(whole + 1) - a * self.allpass_state;
+                self.allpass_state
+            }
+            DelayInterpolation::Cubic => hermite(
+                // The sample after the newest hasn't been written yet
+                self.tap((whole - 1).max(1)),
+                self.tap(whole),
+                self.tap(whole + 1),
+                self.tap(whole + 2),
+                frac,
+            ),
+        }
+    }
+
+    /// Read the input from `delay` samples ago, then write the next input.
+    pub fn process(&mut self, input: f32, delay: f32) -> f32 {
+        let output = self.read(delay);
+        self.write(input);
+        output
+    }
+
+    /// Fill the line with silence.
+    pub fn reset(&mut self) {
+        for sample in self.buffer.iter_mut() {
+            *sample = 0.0;
+        }
+        self.allpass_state = 0.0;
+    }
+
+    /// Sample written `delay` whole samples ago.
+    fn tap(&self, delay: usize) -> f32 {
+        self.buffer[self.write_pos.wrapping_sub(delay) & self.mask]
+    }
+}
+
+/// Delay time of an effect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DelayTime {
+    /// Time in seconds.
+    Seconds(f32),
+    /// Duration of `beats` quarter notes at `bpm` beats per minute, see
+    /// `beats_to_seconds`.
+    Tempo { bpm: f32, beats: f32 },
+}
+
+impl DelayTime {
+    /// Delay time in seconds.
+    pub fn seconds(self) -> f32 {
+        match self {
+            Self::Seconds(seconds) => seconds,
+            Self::Tempo { bpm, beats } => crate::beats_to_seconds(beats, bpm),
+        }
+    }
+}
+
+/// Stereo feedback echo. Each repeat passes through a low pass and high pass
+/// filter, so repeats get darker and thinner as they fade. In ping-pong mode
+/// the input is mixed to mono and the repeats alternate between the left and
+/// right channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Echo {
+    lines: [DelayLine; 2],
+    low_pass: [Biquad; 2],
+    high_pass: [Biquad; 2],
+    sample_rate: f32,
+    delay: f32,
+    target_delay: f32,
+    glide_coef: f32,
+    feedback: f32,
+    mix: f32,
+    ping_pong: bool,
+}
+
+impl Echo {
+    /// Create a new echo with delay times of up to `max_seconds`. Defaults to
+    /// a feedback of 0.4, a mix of 0.3, and filters at 8 kHz and 100 Hz.
+    pub fn new(max_seconds: f32, time: DelayTime, sample_rate: f32) -> Self {
+        let max_delay = (max_seconds * sample_rate).ceil() as usize;
+        let line = DelayLine::new(max_delay, DelayInterpolation::Cubic);
+        let low_pass = Biquad::new(BiquadMode::LowPass, 8000.0, 0.707, sample_rate);
+        let high_pass = Biquad::new(BiquadMode::HighPass, 100.0, 0.707, sample_rate);
+
+        let mut echo = Self {
+            lines: [line.clone(), line],
+            low_pass: [low_pass; 2],
+            high_pass: [high_pass; 2],
+            sample_rate,
+            delay: 0.0,
+            target_delay: 0.0,
+            // Delay time changes glide over about 50 ms
+            glide_coef: (-1.0 / (0.05 * sample_rate)).exp(),
+            feedback: 0.4,
+            mix: 0.3,
+            ping_pong: false,
+        };
+        echo.set_time(time);
+        echo.delay = echo.target_delay;
+        echo
+    }
+
+    /// Set the delay time, gliding to it to avoid clicks.
+    pub fn set_time(&mut self, time: DelayTime) {
+        self.target_delay = time.seconds() * self.sample_rate;
+    }
+
+    /// Set the amount of each repeat fed back into the delay, from 0.0 to 1.0.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = f32::clamp_component(feedback, 0.0, 1.0);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the echoes at 1.0.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+
+    /// Set whether the repeats alternate between the left and right channels.
+    pub fn set_ping_pong(&mut self, ping_pong: bool) {
+        self.ping_pong = ping_pong;
+    }
+
+    /// Set the cutoff in hertz of the low pass filter on the repeats.
+    pub fn set_damping(&mut self, cutoff: f32) {
+        for filter in self.low_pass.iter_mut() {
+            filter.set_cutoff(cutoff);
+        }
+    }
+
+    /// Set the cutoff in hertz of the high pass filter on the repeats.
+    pub fn set_low_cut(&mut self, cutoff: f32) {
+        for filter in self.high_pass.iter_mut() {
+            filter.set_cutoff(cutoff);
+        }
+    }
+
+    /// Set the interpolation used to read the delay lines.
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        for line in self.lines.iter_mut() {
+            line.set_interpolation(interpolation);
+        }
+    }
+}
+
+impl Effect for Echo {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        self.delay = self.target_delay + (self.delay - self.target_delay) * self.glide_coef;
+
+        let mut wet = [0.0; 2];
+        for (i, out) in wet.iter_mut().enumerate() {
+            let repeat = self.lines[i].read(self.delay);
+            *out = self.high_pass[i].process(self.low_pass[i].process(repeat));
+        }
+
+        if self.ping_pong {
+            let mono = (frame[0] + frame[1]) * 0.5;
+            self.lines[0].write(mono + wet[1] * self.feedback);
+            self.lines[1].write(wet[0] * self.feedback);
+        } else {
+            self.lines[0].write(frame[0] + wet[0] * self.feedback);
+            self.lines[1].write(frame[1] + wet[1] * self.feedback);
+        }
+
+        [
+            frame[0] * (1.0 - self.mix) + wet[0] * self.mix,
+            frame[1] * (1.0 - self.mix) + wet[1] * self.mix,
+        ]
+    }
+
+    fn reset(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.reset();
+        }
+        for filter in self.low_pass.iter_mut().chain(self.high_pass.iter_mut()) {
+            filter.reset();
+        }
+    }
+}
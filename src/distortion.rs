@@ -0,0 +1,282 @@
+use crate::{
+    effect::Effect,
+    filter::{Biquad, BiquadMode, Filter},
+    FloatComponent,
+};
+
+/// Offset added before the tube curve so the positive half clips sooner.
+const TUBE_BIAS: f32 = 0.3;
+
+/// Q of each biquad in an 8th order Butterworth low pass.
+const BUTTERWORTH_Q: [f32; 4] = [0.5098, 0.6013, 0.9000, 2.5629];
+
+/// Transfer curve of a waveshaper.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShaperCurve {
+    /// Smooth saturation approaching ±1.0.
+    Tanh,
+    /// Cubic soft clip reaching ±1.0 at an input of ±1.0.
+    Cubic,
+    /// Clamp to ±1.0.
+    HardClip,
+    /// Reflect anything past ±1.0 back towards zero, folding loud inputs many
+    /// times over.
+    Foldback,
+    /// Asymmetric saturation approaching ±1.0 which clips the positive half
+    /// sooner, adding even harmonics.
+    Tube,
+}
+
+/// Apply a waveshaper curve to one sample.
+pub fn shape(curve: ShaperCurve, x: f32) -> f32 {
+    match curve {
+        ShaperCurve::Tanh => x.tanh(),
+        ShaperCurve::Cubic => {
+            let x = f32::clamp_component(x, -1.0, 1.0);
+            1.5 * x - 0.5 * x * x * x
+        }
+        ShaperCurve::HardClip => f32::clamp_component(x, -1.0, 1.0),
+        ShaperCurve::Foldback => ((x - 1.0).rem_euclid(4.0) - 2.0).abs() - 1.0,
+        ShaperCurve::Tube => {
+            // Scale each half of the biased curve to reach ±1.0
+            let bias = TUBE_BIAS.tanh();
+            let y = (x + TUBE_BIAS).tanh() - bias;
+            if y > 0.0 {
+                y / (1.0 - bias)
+            } else {
+                y / (1.0 + bias)
+            }
+        }
+    }
+}
+
+/// Oversampling factor of a waveshaper.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Oversampling {
+    /// Run at the sample rate.
+    None,
+    /// Run at twice the sample rate.
+    X2,
+    /// Run at four times the sample rate.
+    X4,
+}
+
+impl Oversampling {
+    /// Multiple of the sample rate.
+    pub fn factor(self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::X2 => 2,
+            Self::X4 => 4,
+        }
+    }
+}
+
+/// Upsampler and downsampler running a function at a multiple of the sample
+/// rate, with low pass filters removing everything above the original Nyquist
+/// frequency.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Oversampler {
+    factor: usize,
+    up: [Biquad; 4],
+    down: [Biquad; 4],
+}
+
+impl Oversampler {
+    fn new(oversampling: Oversampling, sample_rate: f32) -> Self {
+        let factor = oversampling.factor();
+        let rate = sample_rate * factor as f32;
+        let low_pass =
+            BUTTERWORTH_Q.map(|q| Biquad::new(BiquadMode::LowPass, sample_rate * 0.45, q, rate));
+        Self {
+            factor,
+            up: low_pass,
+            down: low_pass,
+        }
+    }
+
+    fn process(&mut self, input: f32, mut f: impl FnMut(f32) -> f32) -> f32 {
+        if self.factor == 1 {
+            return f(input);
+        }
+
+        let mut output = 0.0;
+        for i in 0..self.factor {
+            // Zero stuffing spreads the energy over the new samples, so make
+            // up the gain
+            let mut x = if i == 0 {
+                input * self.factor as f32
+            } else {
+                0.0
+            };
+            x = self.up.iter_mut().fold(x, |x, filter| filter.process(x));
+            x = f(x);
+            output = self.down.iter_mut().fold(x, |x, filter| filter.process(x));
+        }
+        output
+    }
+
+    fn reset(&mut self) {
+        for filter in self.up.iter_mut().chain(self.down.iter_mut()) {
+            filter.reset();
+        }
+    }
+}
+
+/// Stereo waveshaping distortion with drive, a DC blocker to remove the
+/// offset of the asymmetric curves, and optional oversampling to reduce
+/// aliasing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distortion {
+    curve: ShaperCurve,
+    oversamplers: [Oversampler; 2],
+    sample_rate: f32,
+    drive: f32,
+    mix: f32,
+    dc_coef: f32,
+    dc_input: [f32; 2],
+    dc_output: [f32; 2],
+}
+
+impl Distortion {
+    /// Create a new distortion with a drive of 0 dB, a mix of 1.0, and no
+    /// oversampling.
+    pub fn new(curve: ShaperCurve, sample_rate: f32) -> Self {
+        let oversampler = Oversampler::new(Oversampling::None, sample_rate);
+        Self {
+            curve,
+            oversamplers: [oversampler; 2],
+            sample_rate,
+            drive: 1.0,
+            mix: 1.0,
+            // Block everything below about 10 Hz
+            dc_coef: (-core::f32::consts::TAU * 10.0 / sample_rate).exp(),
+            dc_input: [0.0; 2],
+            dc_output: [0.0; 2],
+        }
+    }
+
+    /// Set the transfer curve.
+    pub fn set_curve(&mut self, curve: ShaperCurve) {
+        self.curve = curve;
+    }
+
+    /// Set the gain in decibels before the waveshaper.
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = 10.0f32.powf(drive / 20.0);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the distorted signal
+    /// at 1.0.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+
+    /// Set the oversampling factor, clearing the oversampling filters.
+    pub fn set_oversampling(&mut self, oversampling: Oversampling) {
+        self.oversamplers = [Oversampler::new(oversampling, self.sample_rate); 2];
+    }
+}
+
+impl Effect for Distortion {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let (curve, drive) = (self.curve, self.drive);
+        let mut out = [0.0; 2];
+        for (channel, out) in out.iter_mut().enumerate() {
+            let wet =
+                self.oversamplers[channel].process(frame[channel], |x| shape(curve, x * drive));
+
+            let blocked = wet - self.dc_input[channel] + self.dc_coef * self.dc_output[channel];
+            self.dc_input[channel] = wet;
+            self.dc_output[channel] = blocked;
+
+            *out = frame[channel] * (1.0 - self.mix) + blocked * self.mix;
+        }
+        out
+    }
+
+    fn reset(&mut self) {
+        for oversampler in self.oversamplers.iter_mut() {
+            oversampler.reset();
+        }
+        self.dc_input = [0.0; 2];
+        self.dc_output = [0.0; 2];
+    }
+}
+
+/// Stereo bitcrusher which quantizes to a lower bit depth and holds samples
+/// to imitate a lower sample rate, aliasing on purpose.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bitcrusher {
+    sample_rate: f32,
+    levels: f32,
+    hold_rate: f32,
+    hold_phase: f32,
+    held: [f32; 2],
+    mix: f32,
+}
+
+impl Bitcrusher {
+    /// Create a new bitcrusher with a bit depth and a reduced sample rate in
+    /// hertz, and a mix of 1.0.
+    pub fn new(bits: f32, reduced_rate: f32, sample_rate: f32) -> Self {
+        let mut crusher = Self {
+            sample_rate,
+            levels: 0.0,
+            hold_rate: 0.0,
+            // Take a sample on the first frame
+            hold_phase: 1.0,
+            held: [0.0; 2],
+            mix: 1.0,
+        };
+        crusher.set_bits(bits);
+        crusher.set_reduced_rate(reduced_rate);
+        crusher
+    }
+
+    /// Set the bit depth from 1.0 to 24.0. The signal is quantized to 2^bits
+    /// levels spread evenly between -1.0 and 1.0, each in the middle of its
+    /// step, so 1 bit gives ±0.5. Fractional depths round 2^bits to the
+    /// nearest whole number of levels, and an odd number has a level at zero.
+    pub fn set_bits(&mut self, bits: f32) {
+        self.levels = 2.0f32.powf(f32::clamp_component(bits, 1.0, 24.0)).round();
+    }
+
+    /// Set the rate in hertz at which new samples are taken, up to the sample
+    /// rate.
+    pub fn set_reduced_rate(&mut self, reduced_rate: f32) {
+        self.hold_rate = f32::clamp_component(reduced_rate / self.sample_rate, 0.0, 1.0);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the crushed signal at
+    /// 1.0.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+}
+
+impl Effect for Bitcrusher {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        if self.hold_phase >= 1.0 {
+            self.hold_phase -= 1.0;
+            let step = 2.0 / self.levels;
+            for (held, &sample) in self.held.iter_mut().zip(frame.iter()) {
+                // Index of the step holding the sample, keeping ±1.0 in range
+                let level =
+                    f32::clamp_component(((sample + 1.0) / step).floor(), 0.0, self.levels - 1.0);
+                *held = (level + 0.5) * step - 1.0;
+            }
+        }
+        self.hold_phase += self.hold_rate;
+
+        [
+            frame[0] * (1.0 - self.mix) + self.held[0] * self.mix,
+            frame[1] * (1.0 - self.mix) + self.held[1] * self.mix,
+        ]
+    }
+
+    fn reset(&mut self) {
+        self.hold_phase = 1.0;
+        self.held = [0.0; 2];
+    }
+}
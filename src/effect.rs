@@ -0,0 +1,32 @@
+/// Trait for stereo effects which process one frame at a time.
+pub trait Effect {
+    /// Process one stereo frame of `[left, right]`.
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2];
+
+    /// Clear the effect state as if it had only been fed silence.
+    fn reset(&mut self);
+
+    /// Process a buffer of interleaved mono or stereo samples in place, in the
+    /// layout passed to `write_wave_file`. Mono input is processed as a stereo
+    /// frame with the output channels averaged.
+    ///
+    /// Panics if `num_channels` isn't 1 or 2.
+    fn process_block(&mut self, block: &mut [f32], num_channels: usize) {
+        match num_channels {
+            1 => {
+                for sample in block.iter_mut() {
+                    let [left, right] = self.process_frame([*sample; 2]);
+                    *sample = (left + right) * 0.5;
+                }
+            }
+            2 => {
+                for frame in block.chunks_exact_mut(2) {
+                    let [left, right] = self.process_frame([frame[0], frame[1]]);
+                    frame[0] = left;
+                    frame[1] = right;
+                }
+            }
+            _ => panic!("effects only support mono or stereo buffers"),
+        }
+    }
+}
@@ -0,0 +1,518 @@
+/// Shape of the envelope's attack, decay, and release segments.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Curve {
+    /// Constant rate of change.
+    Linear,
+    /// Fast at first and slowing as the target is approached, like the
+    /// capacitor charging of an analog envelope.
+    Exponential,
+}
+
+/// How the envelope responds to a gate on while it's already active.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TriggerMode {
+    /// Every gate on restarts the attack from the current level.
+    Retrigger,
+    /// A gate on while the gate is already held continues the current
+    /// envelope, a gate on during release restarts the attack from the current
+    /// level.
+    Legato,
+}
+
+/// Current segment of an envelope.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Stage {
+    /// Not playing, the output is 0.
+    Idle,
+    /// Rising to 1.0 after a gate on.
+    Attack,
+    /// Falling from 1.0 to the sustain level.
+    Decay,
+    /// Holding the sustain level while the gate is on.
+    Sustain,
+    /// Falling to 0.0 after a gate off.
+    Release,
+}
+
+/// Overshoot of the exponential attack target, relative to the segment height.
+const ATTACK_RATIO: f32 = 0.3;
+/// Overshoot of the exponential decay and release targets.
+const DECAY_RATIO: f32 = 0.001;
+
+/// Attack, decay, sustain, release envelope ranging from 0.0 to 1.0. Times are
+/// in seconds and the sustain level ranges from 0.0 to 1.0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adsr {
+    sample_rate: f32,
+    attack: f32,
+    decay: f32,
+    sustain: f32,
+    release: f32,
+    curve: Curve,
+    mode: TriggerMode,
+    gate: bool,
+    stage: Stage,
+    level: f32,
+    // Samples into the current segment and left until its target
+    elapsed: u32,
+    remaining: u32,
+    // Each sample, level = level * coef + base until the target is reached
+    coef: f32,
+    base: f32,
+    target: f32,
+}
+
+impl Adsr {
+    /// Create a new idle envelope with linear segments in retrigger mode.
+    pub fn new(sample_rate: f32, attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self {
+            sample_rate,
+            attack,
+            decay,
+            sustain,
+            release,
+            curve: Curve::Linear,
+            mode: TriggerMode::Retrigger,
+            gate: false,
+            stage: Stage::Idle,
+            level: 0.0,
+            elapsed: 0,
+            remaining: 0,
+            coef: 1.0,
+            base: 0.0,
+            target: 0.0,
+        }
+    }
+
+    /// Set the attack time in seconds. A change during the attack keeps the
+    /// time already spent in it.
+    pub fn set_attack(&mut self, attack: f32) {
+        self.attack = attack;
+        self.update_stage(Stage::Attack);
+    }
+
+    /// Set the decay time in seconds. A change during the decay keeps the time
+    /// already spent in it.
+    pub fn set_decay(&mut self, decay: f32) {
+        self.decay = decay;
+        self.update_stage(Stage::Decay);
+    }
+
+    /// Set the sustain level. A change while sustaining glides to the new level
+    /// over the decay time.
+    pub fn set_sustain(&mut self, sustain: f32) {
+        self.sustain = sustain;
+        if self.stage == Stage::Sustain {
+            self.start_stage(Stage::Decay);
+        } else {
+            self.update_stage(Stage::Decay);
+        }
+    }
+
+    /// Set the release time in seconds. A change during the release keeps the
+    /// time already spent in it.
+    pub fn set_release(&mut self, release: f32) {
+        self.release = release;
+        self.update_stage(Stage::Release);
+    }
+
+    /// Set the shape of the attack, decay, and release segments.
+    pub fn set_curve(&mut self, curve: Curve) {
+        self.curve = curve;
+        self.update_stage(self.stage);
+    }
+
+    /// Set how the envelope responds to a gate on while already active.
+    pub fn set_trigger_mode(&mut self, mode: TriggerMode) {
+        self.mode = mode;
+    }
+
+    /// Start a note.
+    pub fn gate_on(&mut self) {
+        if !(self.mode == TriggerMode::Legato && self.gate) {
+            self.start_stage(Stage::Attack);
+        }
+        self.gate = true;
+    }
+
+    /// Release a note.
+    pub fn gate_off(&mut self) {
+        self.gate = false;
+        if self.stage != Stage::Idle {
+            self.start_stage(Stage::Release);
+        }
+    }
+
+    /// Stop immediately and return to idle.
+    pub fn reset(&mut self) {
+        self.gate = false;
+        self.start_stage(Stage::Idle);
+    }
+
+    /// Current segment of the envelope.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    /// Whether the envelope is producing output, i.e. it isn't idle.
+    pub fn is_active(&self) -> bool {
+        self.stage != Stage::Idle
+    }
+
+    /// Advance the envelope by one sample and return its level.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> f32 {
+        match self.stage {
+            Stage::Idle | Stage::Sustain => {}
+            Stage::Attack | Stage::Decay | Stage::Release => {
+                self.level = self.level * self.coef + self.base;
+                self.elapsed += 1;
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.level = self.target;
+                    self.start_stage(match self.stage {
+                        Stage::Attack => Stage::Decay,
+                        Stage::Decay => Stage::Sustain,
+                        _ => Stage::Idle,
+                    });
+                }
+            }
+        }
+        self.level
+    }
+
+    /// Fill a buffer with consecutive envelope levels.
+    pub fn process_block(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.next();
+        }
+    }
+
+    /// Multiply a buffer of samples by consecutive envelope levels.
+    pub fn apply(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample *= self.next();
+        }
+    }
+
+    /// Recalculate the rest of the segment after a change to one of its
+    /// parameters, if it's the current segment.
+    fn update_stage(&mut self, stage: Stage) {
+        if self.stage == stage {
+            self.continue_stage(stage, self.elapsed);
+        }
+    }
+
+    /// Begin a segment from the current level.
+    fn start_stage(&mut self, stage: Stage) {
+        self.continue_stage(stage, 0);
+    }
+
+    /// Move from the current level to the end of a segment in the time left
+    /// after `elapsed` samples of it.
+    fn continue_stage(&mut self, stage: Stage, elapsed: u32) {
+        self.stage = stage;
+        let (target, time, ratio) = match stage {
+            Stage::Idle => {
+                self.level = 0.0;
+                return;
+            }
+            Stage::Sustain => {
+                self.level = self.sustain;
+                return;
+            }
+            Stage::Attack => (1.0, self.attack, ATTACK_RATIO),
+            Stage::Decay => (self.sustain, self.decay, DECAY_RATIO),
+            Stage::Release => (0.0, self.release, DECAY_RATIO),
+        };
+
+        let samples = (time * self.sample_rate).round().max(1.0) as u32;
+        self.elapsed = elapsed;
+        self.remaining = samples.saturating_sub(elapsed).max(1);
+        self.target = target;
+        let (coef, base) =
+            segment_coefficients(self.level, target, self.remaining as f32, self.curve, ratio);
+        self.coef = coef;
+        self.base = base;
+    }
+}
+
+/// Coefficients for a segment from `start` to `target` over `samples` samples,
+/// where each sample `level = level * coef + base`.
+fn segment_coefficients(
+    start: f32,
+    target: f32,
+    samples: f32,
+    curve: Curve,
+    ratio: f32,
+) -> (f32, f32) {
+    match curve {
+        Curve::Linear => (1.0, (target - start) / samples),
+        Curve::Exponential => {
+            // Approach a point past the target so it's reached in time
+            let overshoot = target + (target - start) * ratio;
+            let coef = (ratio / (1.0 + ratio)).powf(samples.recip());
+            (coef, overshoot * (1.0 - coef))
+        }
+    }
+}
+
+/// Point of a multi-segment envelope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Breakpoint {
+    /// Time in seconds to reach this point from the previous one.
+    pub time: f32,
+    /// Level of the envelope at this point.
+    pub level: f32,
+    /// Shape of the segment leading to this point.
+    pub curve: Curve,
+}
+
+impl Breakpoint {
+    /// Create a new breakpoint.
+    pub fn new(time: f32, level: f32, curve: Curve) -> Self {
+        Self { time, level, curve }
+    }
+}
+
+/// Envelope made of any number of breakpoints with optional sustain and loop
+/// points. Levels aren't limited to 0.0 to 1.0, so the output can drive
+/// amplitude, pitch in semitones, filter cutoff, or any other parameter.
+///
+/// On gate on, the envelope moves from its current level through each
+/// breakpoint in turn. While the gate is held it stops at the sustain point,
+/// or jumps from the loop end back to the segment after the loop start. On
+/// gate off it moves from its current level to the point after the sustain or
+/// loop end point, whichever is later. Without a sustain or loop point the
+/// envelope plays through regardless of the gate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiSegmentEnvelope {
+    sample_rate: f32,
+    points: Vec<Breakpoint>,
+    sustain_point: Option<usize>,
+    loop_points: Option<(usize, usize)>,
+    gate: bool,
+    holding: bool,
+    // Index of the breakpoint being approached, equal to the number of points
+    // when the envelope has finished
+    segment: usize,
+    remaining: u32,
+    level: f32,
+    coef: f32,
+    base: f32,
+    target: f32,
+}
+
+impl MultiSegmentEnvelope {
+    /// Create a new envelope which starts at `start_level` before the first
+    /// gate on.
+    pub fn new(sample_rate: f32, start_level: f32, points: Vec<Breakpoint>) -> Self {
+        let segment = points.len();
+        Self {
+            sample_rate,
+            points,
+            sustain_point: None,
+            loop_points: None,
+            gate: false,
+            holding: false,
+            segment,
+            remaining: 0,
+            level: start_level,
+            coef: 1.0,
+            base: 0.0,
+            target: start_level,
+        }
+    }
+
+    /// Set the index of the breakpoint held while the gate is on.
+    ///
+    /// Panics if the index is out of range.
+    pub fn set_sustain_point(&mut self, sustain_point: Option<usize>) {
+        if let Some(index) = sustain_point {
+            assert!(index < self.points.len(), "sustain point out of range");
+        }
+        self.sustain_point = sustain_point;
+    }
+
+    /// Set the indices of the first and last breakpoints of a loop repeated
+    /// while the gate is on. Reaching the end point continues toward the point
+    /// after the start point.
+    ///
+    /// Panics if the start isn't before the end or the end is out of range.
+    pub fn set_loop_points(&mut self, loop_points: Option<(usize, usize)>) {
+        if let Some((start, end)) = loop_points {
+            assert!(start < end, "loop start must be before loop end");
+            assert!(end < self.points.len(), "loop end out of range");
+        }
+        self.loop_points = loop_points;
+    }
+
+    /// Start the envelope from the first breakpoint.
+    pub fn gate_on(&mut self) {
+        self.gate = true;
+        self.holding = false;
+        self.start_segment(0);
+    }
+
+    /// Release the envelope, moving from the current level to the breakpoint
+    /// after the sustain or loop end point.
+    pub fn gate_off(&mut self) {
+        self.gate = false;
+        self.holding = false;
+        if let Some(release) = self.release_point() {
+            if self.segment <= release {
+                self.start_segment(release + 1);
+            }
+        }
+    }
+
+    /// Stop immediately, jumping to `level`.
+    pub fn reset(&mut self, level: f32) {
+        self.gate = false;
+        self.holding = false;
+        self.segment = self.points.len();
+        self.level = level;
+    }
+
+    /// Whether the envelope is still moving or holding a sustain point.
+    pub fn is_active(&self) -> bool {
+        self.segment < self.points.len()
+    }
+
+    /// Advance the envelope by one sample and return its level.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> f32 {
+        if self.is_active() && !self.holding {
+            self.level = self.level * self.coef + self.base;
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                self.level = self.target;
+                self.arrive();
+            }
+        }
+        self.level
+    }
+
+    /// Fill a buffer with consecutive envelope levels.
+    pub fn process_block(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.next();
+        }
+    }
+
+    /// Multiply a buffer of samples by consecutive envelope levels.
+    pub fn apply(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample *= self.next();
+        }
+    }
+
+    /// Index of the last breakpoint before the release segments.
+    fn release_point(&self) -> Option<usize> {
+        let loop_end = self.loop_points.map(|(_, end)| end);
+        self.sustain_point.max(loop_end)
+    }
+
+    /// Choose the next segment after reaching a breakpoint.
+    fn arrive(&mut self) {
+        let index = self.segment;
+        if self.gate {
+            if self.sustain_point == Some(index) {
+                self.holding = true;
+                return;
+            }
+            if let Some((start, end)) = self.loop_points {
+                if index == end {
+                    self.start_segment(start + 1);
+                    return;
+                }
+            }
+        }
+        self.start_segment(index + 1);
+    }
+
+    /// Begin moving from the current level toward a breakpoint.
+    fn start_segment(&mut self, index: usize) {
+        self.segment = index.min(self.points.len());
+        let point = match self.points.get(index) {
+            Some(&point) => point,
+            None => return,
+        };
+
+        let samples = (point.time * self.sample_rate).round().max(1.0);
+        let ratio = if point.level > self.level {
+            ATTACK_RATIO
+        } else {
+            DECAY_RATIO
+        };
+        let (coef, base) =
+            segment_coefficients(self.level, point.level, samples, point.curve, ratio);
+        self.remaining = samples as u32;
+        self.coef = coef;
+        self.base = base;
+        self.target = point.level;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Number of samples spent in `stage`, calling `update` before each one.
+    fn stage_length(env: &mut Adsr, stage: Stage, mut update: impl FnMut(&mut Adsr)) -> u32 {
+        let mut samples = 0;
+        while env.stage() == stage && samples < 100_000 {
+            update(env);
+            env.next();
+            samples += 1;
+        }
+        samples
+    }
+
+    /// Envelope at 1 kHz which has finished its single sample attack.
+    fn decaying() -> Adsr {
+        let mut env = Adsr::new(1000.0, 0.0, 0.02, 0.5, 0.03);
+        env.gate_on();
+        env.next();
+        assert_eq!(env.stage(), Stage::Decay);
+        env
+    }
+
+    #[test]
+    fn linear_decay_takes_its_time_in_samples() {
+        let mut env = decaying();
+        assert_eq!(stage_length(&mut env, Stage::Decay, |_| {}), 20);
+        assert_eq!(env.next(), 0.5);
+    }
+
+    #[test]
+    fn exponential_decay_takes_its_time_in_samples() {
+        let mut env = decaying();
+        env.set_curve(Curve::Exponential);
+        assert_eq!(stage_length(&mut env, Stage::Decay, |_| {}), 20);
+    }
+
+    #[test]
+    fn other_stage_parameters_leave_the_segment_alone() {
+        let mut env = decaying();
+        let samples = stage_length(&mut env, Stage::Decay, |env| {
+            env.set_attack(0.01);
+            env.set_release(0.03);
+        });
+        assert_eq!(samples, 20);
+    }
+
+    #[test]
+    fn current_stage_parameters_keep_the_elapsed_time() {
+        let mut env = decaying();
+        let samples = stage_length(&mut env, Stage::Decay, |env| {
+            env.set_decay(0.02);
+            env.set_curve(Curve::Linear);
+        });
+        assert_eq!(samples, 20);
+
+        env.gate_off();
+        let samples = stage_length(&mut env, Stage::Release, |env| env.set_release(0.03));
+        assert_eq!(samples, 30);
+    }
+}
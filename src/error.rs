@@ -0,0 +1,58 @@
+use std::{fmt, io, num::TryFromIntError};
+
+/// Errors which can occur when reading or writing WAVE files.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying reader or writer failed.
+    Io(io::Error),
+    /// The length of the supplied data doesn't match the expected length.
+    LengthMismatch { expected: usize, actual: usize },
+    /// A size or rate doesn't fit in its field of the WAVE header, which
+    /// limits files to 4 GiB.
+    SizeOverflow,
+    /// The format, bit depth, or channel configuration isn't supported.
+    UnsupportedFormat(&'static str),
+    /// A chunk is missing, truncated, or contains inconsistent values.
+    MalformedChunk(&'static str),
+    /// An earlier write by a `WaveWriter` failed, so the amount of data in the
+    /// file is unknown.
+    Poisoned,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "Data length {} not equal to the expected length {}",
+                actual, expected
+            ),
+            Self::SizeOverflow => write!(f, "Data size exceeds the maximum WAVE file size"),
+            Self::UnsupportedFormat(reason) => write!(f, "Unsupported format: {}", reason),
+            Self::MalformedChunk(reason) => write!(f, "Malformed chunk: {}", reason),
+            Self::Poisoned => write!(f, "An earlier write to the WAVE writer failed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<TryFromIntError> for Error {
+    fn from(_: TryFromIntError) -> Self {
+        Self::SizeOverflow
+    }
+}
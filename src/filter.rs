@@ -0,0 +1,456 @@
+use crate::FloatComponent;
+
+/// Trait for filters which process one sample at a time.
+pub trait Filter {
+    /// Filter the next input sample and return the output.
+    fn process(&mut self, input: f32) -> f32;
+
+    /// Clear the filter state as if it had only been fed silence.
+    fn reset(&mut self);
+
+    /// Filter a buffer in place.
+    fn process_block(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+/// Response of a biquad filter, from the Audio EQ Cookbook by Robert
+/// Bristow-Johnson.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BiquadMode {
+    /// Pass frequencies below the cutoff.
+    LowPass,
+    /// Pass frequencies above the cutoff.
+    HighPass,
+    /// Pass frequencies around the cutoff with a peak gain of 0 dB.
+    BandPass,
+    /// Reject frequencies around the cutoff.
+    Notch,
+    /// Pass all frequencies, shifting the phase around the cutoff.
+    AllPass,
+    /// Boost or cut frequencies around the cutoff by the gain.
+    Peak,
+    /// Boost or cut frequencies below the cutoff by the gain.
+    LowShelf,
+    /// Boost or cut frequencies above the cutoff by the gain.
+    HighShelf,
+}
+
+/// Normalized biquad coefficients, with `a0` divided out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Coefficients {
+    fn new(mode: BiquadMode, cutoff: f32, q: f32, gain: f32, sample_rate: f32) -> Self {
+        let w0 = core::f32::consts::TAU * cutoff / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * q);
+        // Square root of the linear gain, since the peak and shelves split it
+        // between the poles and zeros
+        let a = 10.0f32.powf(gain / 40.0);
+
+        let (b0, b1, b2, a0, a1, a2) = match mode {
+            BiquadMode::LowPass => (
+                (1.0 - cos) * 0.5,
+                1.0 - cos,
+                (1.0 - cos) * 0.5,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            BiquadMode::HighPass => (
+                (1.0 + cos) * 0.5,
+                -(1.0 + cos),
+                (1.0 + cos) * 0.5,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            BiquadMode::BandPass => (alpha, 0.0, -alpha, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            BiquadMode::Notch => (1.0, -2.0 * cos, 1.0, 1.0 + alpha, -2.0 * cos, 1.0 - alpha),
+            BiquadMode::AllPass => (
+                1.0 - alpha,
+                -2.0 * cos,
+                1.0 + alpha,
+                1.0 + alpha,
+                -2.0 * cos,
+                1.0 - alpha,
+            ),
+            BiquadMode::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            BiquadMode::LowShelf => {
+                let shelf = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                    (a + 1.0) + (a - 1.0) * cos + shelf,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - shelf,
+                )
+            }
+            BiquadMode::HighShelf => {
+                let shelf = 2.0 * a.sqrt() * alpha;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                    (a + 1.0) - (a - 1.0) * cos + shelf,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - shelf,
+                )
+            }
+        };
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// Second order IIR filter in transposed direct form II. Changes to the
+/// cutoff, Q, and gain glide to their new values over the smoothing time,
+/// recalculating the coefficients every sample until they arrive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Biquad {
+    mode: BiquadMode,
+    sample_rate: f32,
+    coefficients: Coefficients,
+    // Current and target cutoff, Q, and gain
+    current: [f32; 3],
+    target: [f32; 3],
+    smoothing_coef: f32,
+    s1: f32,
+    s2: f32,
+}
+
+impl Biquad {
+    /// Create a new filter with a cutoff in hertz and a gain of 0 dB. A `q` of
+    /// about 0.707 gives the flattest low and high pass response.
+    pub fn new(mode: BiquadMode, cutoff: f32, q: f32, sample_rate: f32) -> Self {
+        let mut filter = Self {
+            mode,
+            sample_rate,
+            coefficients: Coefficients::default(),
+            current: [0.0; 3],
+            target: [0.0; 3],
+            smoothing_coef: 0.0,
+            s1: 0.0,
+            s2: 0.0,
+        };
+        filter.set_cutoff(cutoff);
+        filter.set_q(q);
+        filter.set_smoothing(0.005);
+        filter.current = filter.target;
+        filter.update_coefficients();
+        filter
+    }
+
+    /// Set the response, taking effect immediately.
+    pub fn set_mode(&mut self, mode: BiquadMode) {
+        self.mode = mode;
+        self.update_coefficients();
+    }
+
+    /// Set the cutoff or center frequency in hertz, limited to just under the
+    /// Nyquist frequency.
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.target[0] = f32::clamp_component(cutoff, 1.0, self.sample_rate * 0.49);
+    }
+
+    /// Set the Q, where higher values give a narrower band or more resonance.
+    pub fn set_q(&mut self, q: f32) {
+        self.target[1] = q.max(0.01);
+    }
+
+    /// Set the gain in decibels of the peak and shelf modes.
+    pub fn set_gain(&mut self, gain: f32) {
+        self.target[2] = gain;
+    }
+
+    /// Set the time in seconds for parameter changes to mostly settle, 0.0 to
+    /// apply them on the next sample.
+    pub fn set_smoothing(&mut self, seconds: f32) {
+        self.smoothing_coef = if seconds > 0.0 {
+            (-1.0 / (seconds * self.sample_rate)).exp()
+        } else {
+            0.0
+        };
+    }
+
+    fn update_coefficients(&mut self) {
+        let [cutoff, q, gain] = self.current;
+        self.coefficients = Coefficients::new(self.mode, cutoff, q, gain, self.sample_rate);
+    }
+}
+
+impl Filter for Biquad {
+    fn process(&mut self, input: f32) -> f32 {
+        if self.current != self.target {
+            for (current, &target) in self.current.iter_mut().zip(self.target.iter()) {
+                *current = target + (*current - target) * self.smoothing_coef;
+                // Snap once the difference is inaudible so the coefficients
+                // stop being recalculated
+                if (*current - target).abs() <= target.abs().max(1.0) * 1e-5 {
+                    *current = target;
+                }
+            }
+            self.update_coefficients();
+        }
+
+        let c = self.coefficients;
+        let output = c.b0 * input + self.s1;
+        self.s1 = c.b1 * input - c.a1 * output + self.s2;
+        self.s2 = c.b2 * input - c.a2 * output;
+        output
+    }
+
+    fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+}
+
+/// Output of a state-variable filter used by its `Filter` impl.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SvfMode {
+    /// 12 dB per octave low pass.
+    LowPass,
+    /// Band pass with a peak gain of 0 dB at a Q of 1.
+    BandPass,
+    /// 12 dB per octave high pass.
+    HighPass,
+    /// Sum of the low and high pass outputs.
+    Notch,
+}
+
+/// Simultaneous outputs of a state-variable filter.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SvfOutput {
+    /// Low pass output.
+    pub low: f32,
+    /// Band pass output.
+    pub band: f32,
+    /// High pass output.
+    pub high: f32,
+    /// Notch output.
+    pub notch: f32,
+}
+
+/// Zero-delay feedback state-variable filter using trapezoidal integrators,
+/// which stays stable while the cutoff is modulated every sample.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StateVariableFilter {
+    mode: SvfMode,
+    sample_rate: f32,
+    cutoff: f32,
+    q: f32,
+    g: f32,
+    k: f32,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl StateVariableFilter {
+    /// Create a new filter with a cutoff in hertz. A `q` of about 0.707 gives
+    /// the flattest low and high pass response.
+    pub fn new(mode: SvfMode, cutoff: f32, q: f32, sample_rate: f32) -> Self {
+        let mut filter = Self {
+            mode,
+            sample_rate,
+            cutoff: 0.0,
+            q: 0.0,
+            g: 0.0,
+            k: 0.0,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        };
+        filter.set_q(q);
+        filter.set_cutoff(cutoff);
+        filter
+    }
+
+    /// Set which output `process` returns.
+    pub fn set_mode(&mut self, mode: SvfMode) {
+        self.mode = mode;
+    }
+
+    /// Cutoff or center frequency in hertz.
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    /// Set the cutoff or center frequency in hertz, limited to just under the
+    /// Nyquist frequency. May be called every sample.
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = f32::clamp_component(cutoff, 1.0, self.sample_rate * 0.49);
+        self.g = (core::f32::consts::PI * self.cutoff / self.sample_rate).tan();
+    }
+
+    /// Set the Q, where higher values give a narrower band or more resonance.
+    pub fn set_q(&mut self, q: f32) {
+        self.q = q.max(0.01);
+        self.k = self.q.recip();
+    }
+
+    /// Filter the next input sample and return every output.
+    pub fn process_all(&mut self, input: f32) -> SvfOutput {
+        let a1 = 1.0 / (1.0 + self.g * (self.g + self.k));
+        let a2 = self.g * a1;
+        let a3 = self.g * a2;
+
+        let v3 = input - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        let high = input - self.k * v1 - v2;
+        SvfOutput {
+            low: v2,
+            band: v1,
+            high,
+            notch: v2 + high,
+        }
+    }
+}
+
+impl Filter for StateVariableFilter {
+    fn process(&mut self, input: f32) -> f32 {
+        let out = self.process_all(input);
+        match self.mode {
+            SvfMode::LowPass => out.low,
+            SvfMode::BandPass => out.band,
+            SvfMode::HighPass => out.high,
+            SvfMode::Notch => out.notch,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+}
+
+/// Loop gain of a saturating ladder at full resonance.
+const SELF_OSCILLATION_GAIN: f32 = 1.05;
+
+/// Moog-style 24 dB per octave ladder low pass filter of four one-pole stages
+/// with zero-delay feedback. With saturation enabled, the signal entering the
+/// ladder is soft clipped with `tanh`, keeping the output bounded for hot
+/// input and at full resonance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LadderFilter {
+    sample_rate: f32,
+    cutoff: f32,
+    resonance: f32,
+    g: f32,
+    drive: f32,
+    saturation: bool,
+    stages: [f32; 4],
+}
+
+impl LadderFilter {
+    /// Create a new filter with a cutoff in hertz and a resonance from 0.0 to
+    /// 1.0, where 1.0 self-oscillates when saturating. Saturation is enabled
+    /// with a drive of 1.0.
+    pub fn new(cutoff: f32, resonance: f32, sample_rate: f32) -> Self {
+        let mut filter = Self {
+            sample_rate,
+            cutoff: 0.0,
+            resonance: 0.0,
+            g: 0.0,
+            drive: 1.0,
+            saturation: true,
+            stages: [0.0; 4],
+        };
+        filter.set_cutoff(cutoff);
+        filter.set_resonance(resonance);
+        filter
+    }
+
+    /// Cutoff frequency in hertz.
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    /// Set the cutoff frequency in hertz, limited to just under the Nyquist
+    /// frequency. May be called every sample.
+    pub fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = f32::clamp_component(cutoff, 1.0, self.sample_rate * 0.49);
+        let g = (core::f32::consts::PI * self.cutoff / self.sample_rate).tan();
+        self.g = g / (1.0 + g);
+    }
+
+    /// Set the resonance from 0.0 to 1.0, where 1.0 self-oscillates when
+    /// saturating and rings for a long time otherwise.
+    pub fn set_resonance(&mut self, resonance: f32) {
+        self.resonance = f32::clamp_component(resonance, 0.0, 1.0);
+    }
+
+    /// Set whether the signal entering the ladder is soft clipped.
+    pub fn set_saturation(&mut self, saturation: bool) {
+        self.saturation = saturation;
+    }
+
+    /// Set the gain before the soft clipper, where higher values distort more.
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive;
+    }
+}
+
+impl Filter for LadderFilter {
+    fn process(&mut self, input: f32) -> f32 {
+        let g = self.g;
+        // Saturation bounds the loop, so full resonance can push the loop gain
+        // just past 1.0 to sustain oscillation without any input
+        let k = if self.saturation {
+            4.0 * SELF_OSCILLATION_GAIN * self.resonance
+        } else {
+            4.0 * self.resonance
+        };
+
+        // Solve the feedback loop for the output the ladder will produce,
+        // where each stage outputs `g * input + (1 - g) * state`
+        let s = 1.0 - g;
+        let state_sum = self.stages[3] * s
+            + g * s * (self.stages[2] + g * (self.stages[1] + g * self.stages[0]));
+        let g4 = g * g * g * g;
+        let estimate = (g4 * input + state_sum) / (1.0 + k * g4);
+
+        let mut x = input - k * estimate;
+        if self.saturation {
+            x = (x * self.drive).tanh();
+        }
+
+        for state in self.stages.iter_mut() {
+            let v = (x - *state) * g;
+            let y = v + *state;
+            *state = y + v;
+            x = y;
+        }
+        x
+    }
+
+    fn reset(&mut self) {
+        self.stages = [0.0; 4];
+    }
+}
@@ -0,0 +1,504 @@
+use crate::{
+    oscillator::{
+        max_harmonics, octave_harmonics, pulse_bl, pulse_polyblep, saw_bl, saw_polyblep, square_bl,
+        triangle_bl, triangle_polyblamp, Oscillator,
+    },
+    sine::{DefaultSine, Sine},
+    FloatComponent,
+};
+
+/// Integer phase accumulator where a full cycle spans the whole range of the
+/// type, so the phase wraps around by overflowing. The frequency is given as a
+/// tuning word added to the phase every sample.
+pub trait Phase: Copy + Default + PartialEq + PartialOrd + core::fmt::Debug {
+    /// Phase halfway through the cycle.
+    const HALF: Self;
+
+    /// Phase from a fraction of a cycle, wrapping values outside of 0.0 to 1.0.
+    fn from_cycles(cycles: f64) -> Self;
+
+    /// Fraction of the cycle from 0.0 to 1.0.
+    fn cycles(self) -> f32;
+
+    /// Fraction of a cycle, treating values over half a cycle as negative.
+    fn signed_cycles(self) -> f32;
+
+    /// Advance the phase by `tuning_word`, wrapping around at the end of the
+    /// cycle.
+    fn advance(&mut self, tuning_word: Self);
+
+    /// Tuning word which advances the phase by `freq` cycles per second.
+    fn tuning_word(freq: f32, sample_rate: f32) -> Self {
+        Self::from_cycles(f64::from(freq) / f64::from(sample_rate))
+    }
+
+    /// Frequency in hertz of a tuning word.
+    fn frequency(self, sample_rate: f32) -> f32 {
+        self.signed_cycles() * sample_rate
+    }
+}
+
+macro_rules! phase {
+    ($t:ty, $signed:ty, $bits:expr) => {
+        impl Phase for $t {
+            const HALF: Self = 1 << ($bits - 1);
+
+            fn from_cycles(cycles: f64) -> Self {
+                // Round to the nearest step with integer casts, then keep the
+                // low bits to wrap into the cycle
+                let steps = cycles * (1u128 << $bits) as f64 + 0.5;
+                let whole = steps as i128;
+                let floor = if whole as f64 > steps {
+                    whole - 1
+                } else {
+                    whole
+                };
+                floor as Self
+            }
+
+            fn cycles(self) -> f32 {
+                // Keep the 24 bits an f32 can hold so the result stays under 1.0
+                (self >> ($bits - 24)) as f32 * (1.0 / (1u32 << 24) as f32)
+            }
+
+            fn signed_cycles(self) -> f32 {
+                self as $signed as f32 * (1.0 / (1u128 << $bits) as f32)
+            }
+
+            fn advance(&mut self, tuning_word: Self) {
+                *self = self.wrapping_add(tuning_word);
+            }
+        }
+    };
+}
+
+phase!(u32, i32, 32);
+phase!(u64, i64, 64);
+
+/// Phase increment per sample of a tuning word as a positive fraction of a
+/// cycle.
+fn increment<P: Phase>(tuning_word: P) -> f32 {
+    tuning_word.signed_cycles().abs()
+}
+
+/// Phase in radians from 0.0 to `TAU`.
+fn radians<P: Phase>(phase: P) -> f32 {
+    phase.cycles() * core::f32::consts::TAU
+}
+
+/// Sine wave oscillator.
+pub fn advance_sine_osc_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    DefaultSine::sin(radians(*phase))
+}
+
+/// Square wave oscillator.
+pub fn advance_square_osc_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    if *phase <= P::HALF {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Saw wave oscillator.
+pub fn advance_saw_osc_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    -((phase.cycles() * 2.0) - 1.0)
+}
+
+/// Triangle wave oscillator.
+pub fn advance_triangle_osc_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    let t = phase.cycles();
+    if *phase <= P::HALF {
+        (t * 4.0) - 1.0
+    } else {
+        ((1.0 - t) * 4.0) - 1.0
+    }
+}
+
+/// Pulse wave oscillator. `width` is the fraction of the cycle spent high,
+/// ranging from 0.0 to 1.0, where 0.5 is a square wave.
+pub fn advance_pulse_osc_fixed<P: Phase>(phase: &mut P, tuning_word: P, width: f32) -> f32 {
+    phase.advance(tuning_word);
+    let width = f32::clamp_component(width, 0.0, 1.0);
+    if phase.cycles() < width {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Band-limited saw wave oscillator. If `harmonics` is 0, one harmonic is used
+/// for every octave under the Nyquist frequency.
+pub fn advance_saw_osc_bl_fixed<P: Phase>(phase: &mut P, tuning_word: P, harmonics: i8) -> f32 {
+    phase.advance(tuning_word);
+    let harmonics = if harmonics == 0 {
+        octave_harmonics(increment(tuning_word))
+    } else {
+        harmonics
+    };
+    saw_bl::<DefaultSine>(radians(*phase), harmonics)
+}
+
+/// Band-limited square wave oscillator. If `harmonics` is 0, the maximum
+/// number of harmonics under the Nyquist frequency will be used, up to
+/// `i8::MAX`.
+pub fn advance_square_osc_bl_fixed<P: Phase>(phase: &mut P, tuning_word: P, harmonics: i8) -> f32 {
+    phase.advance(tuning_word);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(increment(tuning_word), 2.0)
+    } else {
+        harmonics
+    };
+    square_bl::<DefaultSine>(radians(*phase), harmonics)
+}
+
+/// Band-limited triangle wave oscillator. If `harmonics` is 0, the maximum
+/// number of harmonics under the Nyquist frequency will be used, up to
+/// `i8::MAX`.
+pub fn advance_triangle_osc_bl_fixed<P: Phase>(
+    phase: &mut P,
+    tuning_word: P,
+    harmonics: i8,
+) -> f32 {
+    phase.advance(tuning_word);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(increment(tuning_word), 2.0)
+    } else {
+        harmonics
+    };
+    triangle_bl::<DefaultSine>(radians(*phase), harmonics)
+}
+
+/// Band-limited pulse wave oscillator. `width` is the fraction of the cycle
+/// spent high, ranging from 0.0 to 1.0. If `harmonics` is 0, the maximum
+/// number of harmonics under the Nyquist frequency will be used, up to
+/// `i8::MAX`.
+pub fn advance_pulse_osc_bl_fixed<P: Phase>(
+    phase: &mut P,
+    tuning_word: P,
+    width: f32,
+    harmonics: i8,
+) -> f32 {
+    phase.advance(tuning_word);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(increment(tuning_word), 1.0)
+    } else {
+        harmonics
+    };
+    pulse_bl::<DefaultSine>(radians(*phase), width, harmonics)
+}
+
+/// PolyBLEP saw wave oscillator.
+pub fn advance_saw_osc_polyblep_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    saw_polyblep(phase.cycles(), increment(tuning_word))
+}
+
+/// PolyBLEP square wave oscillator.
+pub fn advance_square_osc_polyblep_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    advance_pulse_osc_polyblep_fixed(phase, tuning_word, 0.5)
+}
+
+/// PolyBLEP pulse wave oscillator. `width` is the fraction of the cycle spent
+/// high, ranging from 0.0 to 1.0, where 0.5 is a square wave.
+pub fn advance_pulse_osc_polyblep_fixed<P: Phase>(
+    phase: &mut P,
+    tuning_word: P,
+    width: f32,
+) -> f32 {
+    phase.advance(tuning_word);
+    pulse_polyblep(phase.cycles(), increment(tuning_word), width)
+}
+
+/// PolyBLAMP triangle wave oscillator.
+pub fn advance_triangle_osc_polyblamp_fixed<P: Phase>(phase: &mut P, tuning_word: P) -> f32 {
+    phase.advance(tuning_word);
+    triangle_polyblamp(phase.cycles(), increment(tuning_word))
+}
+
+/// Define the constructor and tuning word accessors shared by the fixed-point
+/// oscillator structs.
+macro_rules! tuning_word_accessors {
+    () => {
+        /// Tuning word added to the phase every sample.
+        pub fn tuning_word(&self) -> P {
+            self.tuning_word
+        }
+
+        /// Set the tuning word added to the phase every sample.
+        pub fn set_tuning_word(&mut self, tuning_word: P) {
+            self.tuning_word = tuning_word;
+        }
+    };
+}
+
+/// Define the `Oscillator` impl of a fixed-point oscillator struct, where
+/// `$advance` is called with the phase and tuning word followed by the listed
+/// fields.
+macro_rules! impl_oscillator_fixed {
+    ($name:ident, $advance:ident $(, $field:ident)*) => {
+        impl<P: Phase> Oscillator for $name<P> {
+            fn next_sample(&mut self) -> f32 {
+                $advance(&mut self.phase, self.tuning_word $(, self.$field)*)
+            }
+
+            fn reset(&mut self) {
+                self.phase = P::default();
+            }
+
+            fn frequency(&self) -> f32 {
+                self.tuning_word.frequency(self.sample_rate)
+            }
+
+            fn set_frequency(&mut self, freq: f32) {
+                self.tuning_word = P::tuning_word(freq, self.sample_rate);
+            }
+
+            fn set_phase(&mut self, phase: f32) {
+                self.phase = P::from_cycles(f64::from(phase));
+            }
+        }
+    };
+}
+
+/// Define a fixed-point oscillator struct which drives one of the
+/// `advance_*_fixed` functions.
+macro_rules! oscillator_fixed {
+    ($(#[$attr:meta])* $name:ident, $advance:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name<P: Phase = u32> {
+            phase: P,
+            tuning_word: P,
+            sample_rate: f32,
+        }
+
+        impl<P: Phase> $name<P> {
+            /// Create a new oscillator starting at phase 0.
+            pub fn new(freq: f32, sample_rate: f32) -> Self {
+                Self::from_tuning_word(P::tuning_word(freq, sample_rate), sample_rate)
+            }
+
+            /// Create a new oscillator starting at phase 0 from a tuning word.
+            pub fn from_tuning_word(tuning_word: P, sample_rate: f32) -> Self {
+                Self {
+                    phase: P::default(),
+                    tuning_word,
+                    sample_rate,
+                }
+            }
+
+            tuning_word_accessors!();
+        }
+
+        impl_oscillator_fixed!($name, $advance);
+    };
+}
+
+/// Define a fixed-point band-limited oscillator struct which drives one of the
+/// `advance_*_bl_fixed` functions.
+macro_rules! oscillator_bl_fixed {
+    ($(#[$attr:meta])* $name:ident, $advance:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name<P: Phase = u32> {
+            phase: P,
+            tuning_word: P,
+            sample_rate: f32,
+            harmonics: i8,
+        }
+
+        impl<P: Phase> $name<P> {
+            /// Create a new oscillator starting at phase 0. If `harmonics` is
+            /// 0, the maximum number of harmonics under the Nyquist frequency
+            /// will be used.
+            pub fn new(freq: f32, sample_rate: f32, harmonics: i8) -> Self {
+                Self::from_tuning_word(P::tuning_word(freq, sample_rate), sample_rate, harmonics)
+            }
+
+            /// Create a new oscillator starting at phase 0 from a tuning word.
+            pub fn from_tuning_word(tuning_word: P, sample_rate: f32, harmonics: i8) -> Self {
+                Self {
+                    phase: P::default(),
+                    tuning_word,
+                    sample_rate,
+                    harmonics,
+                }
+            }
+
+            tuning_word_accessors!();
+
+            /// Set the number of harmonics, 0 for as many as fit under the
+            /// Nyquist frequency.
+            pub fn set_harmonics(&mut self, harmonics: i8) {
+                self.harmonics = harmonics;
+            }
+        }
+
+        impl_oscillator_fixed!($name, $advance, harmonics);
+    };
+}
+
+/// Define a fixed-point pulse oscillator struct which drives one of the
+/// `advance_pulse_*_fixed` functions.
+macro_rules! oscillator_pulse_fixed {
+    ($(#[$attr:meta])* $name:ident, $advance:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name<P: Phase = u32> {
+            phase: P,
+            tuning_word: P,
+            sample_rate: f32,
+            width: f32,
+        }
+
+        impl<P: Phase> $name<P> {
+            /// Create a new oscillator starting at phase 0. `width` is the
+            /// fraction of the cycle spent high, ranging from 0.0 to 1.0.
+            pub fn new(freq: f32, sample_rate: f32, width: f32) -> Self {
+                Self::from_tuning_word(P::tuning_word(freq, sample_rate), sample_rate, width)
+            }
+
+            /// Create a new oscillator starting at phase 0 from a tuning word.
+            pub fn from_tuning_word(tuning_word: P, sample_rate: f32, width: f32) -> Self {
+                Self {
+                    phase: P::default(),
+                    tuning_word,
+                    sample_rate,
+                    width,
+                }
+            }
+
+            tuning_word_accessors!();
+
+            /// Set the fraction of the cycle spent high. May be called every
+            /// sample to modulate the width.
+            pub fn set_pulse_width(&mut self, width: f32) {
+                self.width = width;
+            }
+        }
+
+        impl_oscillator_fixed!($name, $advance, width);
+    };
+}
+
+oscillator_fixed!(
+    /// Fixed-point sine wave oscillator.
+    SineOscFixed,
+    advance_sine_osc_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point square wave oscillator.
+    SquareOscFixed,
+    advance_square_osc_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point saw wave oscillator.
+    SawOscFixed,
+    advance_saw_osc_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point triangle wave oscillator.
+    TriangleOscFixed,
+    advance_triangle_osc_fixed
+);
+oscillator_bl_fixed!(
+    /// Fixed-point band-limited saw wave oscillator.
+    SawOscBlFixed,
+    advance_saw_osc_bl_fixed
+);
+oscillator_bl_fixed!(
+    /// Fixed-point band-limited square wave oscillator.
+    SquareOscBlFixed,
+    advance_square_osc_bl_fixed
+);
+oscillator_bl_fixed!(
+    /// Fixed-point band-limited triangle wave oscillator.
+    TriangleOscBlFixed,
+    advance_triangle_osc_bl_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point PolyBLEP saw wave oscillator.
+    SawOscPolyBlepFixed,
+    advance_saw_osc_polyblep_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point PolyBLEP square wave oscillator.
+    SquareOscPolyBlepFixed,
+    advance_square_osc_polyblep_fixed
+);
+oscillator_fixed!(
+    /// Fixed-point PolyBLAMP triangle wave oscillator.
+    TriangleOscPolyBlampFixed,
+    advance_triangle_osc_polyblamp_fixed
+);
+oscillator_pulse_fixed!(
+    /// Fixed-point pulse wave oscillator.
+    PulseOscFixed,
+    advance_pulse_osc_fixed
+);
+oscillator_pulse_fixed!(
+    /// Fixed-point PolyBLEP pulse wave oscillator.
+    PulseOscPolyBlepFixed,
+    advance_pulse_osc_polyblep_fixed
+);
+
+/// Fixed-point band-limited pulse wave oscillator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PulseOscBlFixed<P: Phase = u32> {
+    phase: P,
+    tuning_word: P,
+    sample_rate: f32,
+    width: f32,
+    harmonics: i8,
+}
+
+impl<P: Phase> PulseOscBlFixed<P> {
+    /// Create a new oscillator starting at phase 0. `width` is the fraction of
+    /// the cycle spent high, ranging from 0.0 to 1.0. If `harmonics` is 0, the
+    /// maximum number of harmonics under the Nyquist frequency will be used.
+    pub fn new(freq: f32, sample_rate: f32, width: f32, harmonics: i8) -> Self {
+        Self::from_tuning_word(
+            P::tuning_word(freq, sample_rate),
+            sample_rate,
+            width,
+            harmonics,
+        )
+    }
+
+    /// Create a new oscillator starting at phase 0 from a tuning word.
+    pub fn from_tuning_word(tuning_word: P, sample_rate: f32, width: f32, harmonics: i8) -> Self {
+        Self {
+            phase: P::default(),
+            tuning_word,
+            sample_rate,
+            width,
+            harmonics,
+        }
+    }
+
+    tuning_word_accessors!();
+
+    /// Set the fraction of the cycle spent high. May be called every sample to
+    /// modulate the width.
+    pub fn set_pulse_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    /// Set the number of harmonics, 0 for as many as fit under the Nyquist
+    /// frequency.
+    pub fn set_harmonics(&mut self, harmonics: i8) {
+        self.harmonics = harmonics;
+    }
+}
+
+impl_oscillator_fixed!(
+    PulseOscBlFixed,
+    advance_pulse_osc_bl_fixed,
+    width,
+    harmonics
+);
@@ -0,0 +1,349 @@
+use crate::{
+    envelope::Adsr,
+    oscillator::{advance_sine_osc_pm, Oscillator},
+};
+
+/// Frequency of an operator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperatorFrequency {
+    /// Multiple of the note frequency.
+    Ratio(f32),
+    /// Frequency in hertz, independent of the note.
+    Fixed(f32),
+}
+
+/// Settings for one operator of a patch. Envelope times are in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OperatorParams {
+    pub frequency: OperatorFrequency,
+    /// Detune in cents.
+    pub detune: f32,
+    /// Output level. For a carrier this is the amplitude, for a modulator it's
+    /// the peak phase deviation in radians, i.e. the modulation index.
+    pub level: f32,
+    /// Amount of the operator's own output fed back to its phase, in radians.
+    pub feedback: f32,
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+impl OperatorParams {
+    /// Create operator settings at a frequency ratio and level with no detune
+    /// or feedback and an organ-like envelope.
+    pub fn new(ratio: f32, level: f32) -> Self {
+        Self {
+            frequency: OperatorFrequency::Ratio(ratio),
+            detune: 0.0,
+            level,
+            feedback: 0.0,
+            attack: 0.001,
+            decay: 0.0,
+            sustain: 1.0,
+            release: 0.05,
+        }
+    }
+
+    /// Set the detune in cents.
+    pub fn detune(mut self, detune: f32) -> Self {
+        self.detune = detune;
+        self
+    }
+
+    /// Set the feedback amount in radians.
+    pub fn feedback(mut self, feedback: f32) -> Self {
+        self.feedback = feedback;
+        self
+    }
+
+    /// Set the envelope times in seconds and the sustain level.
+    pub fn envelope(mut self, attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        self.attack = attack;
+        self.decay = decay;
+        self.sustain = sustain;
+        self.release = release;
+        self
+    }
+}
+
+/// Sine operator with a phase modulation input, self feedback, and an output
+/// level envelope.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Operator {
+    params: OperatorParams,
+    envelope: Adsr,
+    phase: f32,
+    freq: f32,
+    sample_rate: f32,
+    // Last two outputs, averaged for feedback to prevent oscillation
+    history: [f32; 2],
+}
+
+impl Operator {
+    /// Create a new operator for a note frequency.
+    pub fn new(params: OperatorParams, note_freq: f32, sample_rate: f32) -> Self {
+        let mut op = Self {
+            params,
+            envelope: Adsr::new(
+                sample_rate,
+                params.attack,
+                params.decay,
+                params.sustain,
+                params.release,
+            ),
+            phase: 0.0,
+            freq: 0.0,
+            sample_rate,
+            history: [0.0; 2],
+        };
+        op.set_note_frequency(note_freq);
+        op
+    }
+
+    /// Set the note frequency the operator's ratio applies to.
+    pub fn set_note_frequency(&mut self, note_freq: f32) {
+        let freq = match self.params.frequency {
+            OperatorFrequency::Ratio(ratio) => note_freq * ratio,
+            OperatorFrequency::Fixed(freq) => freq,
+        };
+        self.freq = freq * 2.0f32.powf(self.params.detune / 1200.0);
+    }
+
+    /// Start the operator's envelope.
+    pub fn gate_on(&mut self) {
+        self.envelope.gate_on();
+    }
+
+    /// Release the operator's envelope.
+    pub fn gate_off(&mut self) {
+        self.envelope.gate_off();
+    }
+
+    /// Reset the phase, feedback history, and envelope.
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.history = [0.0; 2];
+        self.envelope.reset();
+    }
+
+    /// Whether the operator's envelope is producing output.
+    pub fn is_active(&self) -> bool {
+        self.envelope.is_active()
+    }
+
+    /// Advance the operator by one sample, phase modulated by `modulation` in
+    /// radians.
+    pub fn next_sample(&mut self, modulation: f32) -> f32 {
+        let feedback = self.params.feedback * (self.history[0] + self.history[1]) * 0.5;
+        let out = advance_sine_osc_pm(
+            &mut self.phase,
+            self.freq,
+            self.sample_rate,
+            modulation + feedback,
+        ) * self.params.level
+            * self.envelope.next();
+
+        self.history = [out, self.history[0]];
+        out
+    }
+}
+
+/// Routing of operators. Operators are numbered from 0, each may be modulated
+/// by higher numbered operators, and the carriers are mixed to the output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Algorithm {
+    modulators: Vec<Vec<usize>>,
+    carriers: Vec<usize>,
+}
+
+impl Algorithm {
+    /// Create an algorithm where `modulators[i]` lists the operators which
+    /// modulate operator `i`.
+    ///
+    /// Panics if an operator is modulated by itself or a lower numbered
+    /// operator, or if an index is out of range or there are no carriers.
+    pub fn new(modulators: Vec<Vec<usize>>, carriers: Vec<usize>) -> Self {
+        let len = modulators.len();
+        for (i, sources) in modulators.iter().enumerate() {
+            for &source in sources {
+                assert!(
+                    source > i && source < len,
+                    "operator {} can't be modulated by operator {}",
+                    i,
+                    source
+                );
+            }
+        }
+        assert!(!carriers.is_empty(), "algorithm has no carriers");
+        assert!(carriers.iter().all(|&c| c < len), "carrier out of range");
+
+        Self {
+            modulators,
+            carriers,
+        }
+    }
+
+    /// Four operators in series, 3 -> 2 -> 1 -> 0, for basses and leads.
+    pub fn stack() -> Self {
+        Self::new(vec![vec![1], vec![2], vec![3], vec![]], vec![0])
+    }
+
+    /// Two pairs, 1 -> 0 and 3 -> 2, for electric pianos and bells.
+    pub fn two_pairs() -> Self {
+        Self::new(vec![vec![1], vec![], vec![3], vec![]], vec![0, 2])
+    }
+
+    /// Three operators modulating one carrier, 1, 2, 3 -> 0, for brass.
+    pub fn branch() -> Self {
+        Self::new(vec![vec![1, 2, 3], vec![], vec![], vec![]], vec![0])
+    }
+
+    /// Four carriers without modulation, for drawbar organs.
+    pub fn additive() -> Self {
+        Self::new(vec![vec![], vec![], vec![], vec![]], vec![0, 1, 2, 3])
+    }
+
+    /// Number of operators.
+    pub fn num_operators(&self) -> usize {
+        self.modulators.len()
+    }
+}
+
+/// Algorithm and operator settings describing an FM sound.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patch {
+    pub algorithm: Algorithm,
+    pub operators: Vec<OperatorParams>,
+}
+
+impl Patch {
+    /// Electric piano with a bright tine attack.
+    pub fn electric_piano() -> Self {
+        Self {
+            algorithm: Algorithm::two_pairs(),
+            operators: vec![
+                OperatorParams::new(1.0, 1.0).envelope(0.001, 2.5, 0.0, 0.4),
+                OperatorParams::new(14.0, 0.6).envelope(0.001, 0.2, 0.0, 0.2),
+                OperatorParams::new(1.0, 1.0)
+                    .detune(3.0)
+                    .envelope(0.001, 3.0, 0.1, 0.5),
+                OperatorParams::new(1.0, 1.5).envelope(0.001, 1.0, 0.2, 0.4),
+            ],
+        }
+    }
+
+    /// Bell with inharmonic partials and a long decay.
+    pub fn bell() -> Self {
+        Self {
+            algorithm: Algorithm::two_pairs(),
+            operators: vec![
+                OperatorParams::new(1.0, 1.0).envelope(0.001, 6.0, 0.0, 4.0),
+                OperatorParams::new(3.5, 2.5).envelope(0.001, 4.0, 0.0, 3.0),
+                OperatorParams::new(2.0, 0.5)
+                    .detune(-4.0)
+                    .envelope(0.001, 3.0, 0.0, 2.0),
+                OperatorParams::new(1.41, 3.0).envelope(0.001, 2.0, 0.0, 2.0),
+            ],
+        }
+    }
+
+    /// Punchy bass with a feedback driven attack.
+    pub fn bass() -> Self {
+        Self {
+            algorithm: Algorithm::stack(),
+            operators: vec![
+                OperatorParams::new(1.0, 1.0).envelope(0.001, 0.8, 0.6, 0.1),
+                OperatorParams::new(1.0, 2.0).envelope(0.001, 0.3, 0.3, 0.1),
+                OperatorParams::new(2.0, 1.0)
+                    .feedback(0.8)
+                    .envelope(0.001, 0.1, 0.0, 0.1),
+                OperatorParams::new(1.0, 0.0),
+            ],
+        }
+    }
+}
+
+/// Voice playing one note of an FM patch. The carriers are averaged so the
+/// output ranges from -1.0 to 1.0 when carrier levels are at most 1.0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FmVoice {
+    algorithm: Algorithm,
+    operators: Vec<Operator>,
+    outputs: Vec<f32>,
+    freq: f32,
+}
+
+impl FmVoice {
+    /// Create a new voice for a patch.
+    ///
+    /// Panics if the patch doesn't have one set of operator settings for each
+    /// operator of its algorithm.
+    pub fn new(patch: &Patch, freq: f32, sample_rate: f32) -> Self {
+        assert_eq!(
+            patch.operators.len(),
+            patch.algorithm.num_operators(),
+            "patch needs settings for every operator"
+        );
+
+        Self {
+            algorithm: patch.algorithm.clone(),
+            operators: patch
+                .operators
+                .iter()
+                .map(|&params| Operator::new(params, freq, sample_rate))
+                .collect(),
+            outputs: vec![0.0; patch.operators.len()],
+            freq,
+        }
+    }
+
+    /// Start a note at a frequency in hertz.
+    pub fn note_on(&mut self, freq: f32) {
+        self.set_frequency(freq);
+        self.operators.iter_mut().for_each(Operator::gate_on);
+    }
+
+    /// Release the current note.
+    pub fn note_off(&mut self) {
+        self.operators.iter_mut().for_each(Operator::gate_off);
+    }
+
+    /// Whether any operator's envelope is producing output.
+    pub fn is_active(&self) -> bool {
+        self.operators.iter().any(Operator::is_active)
+    }
+}
+
+impl Oscillator for FmVoice {
+    fn next_sample(&mut self) -> f32 {
+        // Modulators always have higher numbers than the operators they modulate
+        for i in (0..self.operators.len()).rev() {
+            let modulation = self.algorithm.modulators[i]
+                .iter()
+                .map(|&source| self.outputs[source])
+                .sum();
+            self.outputs[i] = self.operators[i].next_sample(modulation);
+        }
+
+        let carriers = &self.algorithm.carriers;
+        carriers.iter().map(|&c| self.outputs[c]).sum::<f32>() / carriers.len() as f32
+    }
+
+    /// Reset every operator's phase and envelope.
+    fn reset(&mut self) {
+        self.operators.iter_mut().for_each(Operator::reset);
+        self.outputs.iter_mut().for_each(|out| *out = 0.0);
+    }
+
+    fn frequency(&self) -> f32 {
+        self.freq
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq;
+        for op in self.operators.iter_mut() {
+            op.set_note_frequency(freq);
+        }
+    }
+}
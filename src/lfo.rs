@@ -0,0 +1,231 @@
+use crate::{
+    noise::Rng,
+    oscillator::{saw_wave, square_wave, triangle_wave, Oscillator},
+    sine::{DefaultSine, Sine},
+};
+
+/// Waveform of a low-frequency oscillator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LfoShape {
+    /// Sine wave starting at 0.0 and rising.
+    Sine,
+    /// Triangle wave starting at -1.0 and rising.
+    Triangle,
+    /// Saw wave starting at 1.0 and falling.
+    Saw,
+    /// Square wave starting at 1.0.
+    Square,
+    /// New random value at the start of every cycle, held for the cycle.
+    SampleAndHold,
+    /// Random values at the start of every cycle, smoothly interpolated.
+    SmoothRandom,
+}
+
+/// Rate of a low-frequency oscillator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LfoRate {
+    /// Cycles per second.
+    Hertz(f32),
+    /// One cycle every `beats` quarter notes at `bpm` beats per minute, see
+    /// `beats_to_seconds`.
+    Tempo { bpm: f32, beats: f32 },
+}
+
+impl LfoRate {
+    /// Rate in hertz. A tempo of 0 beats gives an infinite rate, and 0 beats at
+    /// 0 beats per minute gives NaN.
+    pub fn hertz(self) -> f32 {
+        match self {
+            Self::Hertz(freq) => freq,
+            Self::Tempo { bpm, beats } => crate::beats_to_seconds(beats, bpm).recip(),
+        }
+    }
+}
+
+/// Output range of a low-frequency oscillator.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Polarity {
+    /// Ranging from -1.0 to 1.0, e.g. for vibrato.
+    Bipolar,
+    /// Ranging from 0.0 to 1.0, e.g. for tremolo.
+    Unipolar,
+}
+
+/// Low-frequency oscillator for modulating pitch, amplitude, filter cutoff, or
+/// other parameters. The periodic shapes are the same as the `advance_*`
+/// oscillators and the random shapes use a seeded PRNG so output is
+/// reproducible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lfo {
+    shape: LfoShape,
+    rate: LfoRate,
+    sample_rate: f32,
+    increment: f32,
+    phase_offset: f32,
+    polarity: Polarity,
+    one_shot: bool,
+    seed: u64,
+    rng: Rng,
+    // Phase from 0.0 to 1.0 since the start of the cycle, before the offset
+    phase: f32,
+    finished: bool,
+    held: f32,
+    next_held: f32,
+}
+
+impl Lfo {
+    /// Create a new free-running bipolar LFO.
+    pub fn new(shape: LfoShape, rate: LfoRate, sample_rate: f32) -> Self {
+        let mut lfo = Self {
+            shape,
+            rate,
+            sample_rate,
+            increment: increment(rate, sample_rate),
+            phase_offset: 0.0,
+            polarity: Polarity::Bipolar,
+            one_shot: false,
+            seed: 0,
+            rng: Rng::new(0),
+            phase: 0.0,
+            finished: false,
+            held: 0.0,
+            next_held: 0.0,
+        };
+        lfo.reset();
+        lfo
+    }
+
+    /// Set the waveform.
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// Set the rate in hertz or as a tempo-synced note length.
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        self.rate = rate;
+        self.increment = increment(rate, self.sample_rate);
+    }
+
+    /// Set the fraction of a cycle, from 0.0 to 1.0, that the waveform starts
+    /// at.
+    pub fn set_phase_offset(&mut self, phase_offset: f32) {
+        self.phase_offset = phase_offset - phase_offset.floor();
+    }
+
+    /// Set the output range.
+    pub fn set_polarity(&mut self, polarity: Polarity) {
+        self.polarity = polarity;
+    }
+
+    /// Set whether the LFO stops and holds its final value after one cycle.
+    pub fn set_one_shot(&mut self, one_shot: bool) {
+        self.one_shot = one_shot;
+    }
+
+    /// Set the seed used by the random shapes and restart the cycle.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+
+    /// Whether a one-shot LFO has completed its cycle.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Bipolar output at the current phase.
+    fn evaluate(&self) -> f32 {
+        let mut phase = self.phase_offset + self.phase;
+        if phase > 1.0 {
+            phase -= 1.0;
+        }
+
+        match self.shape {
+            LfoShape::Sine => DefaultSine::sin(phase * core::f32::consts::TAU),
+            LfoShape::Triangle => triangle_wave(phase),
+            LfoShape::Saw => saw_wave(phase),
+            LfoShape::Square => square_wave(phase),
+            LfoShape::SampleAndHold => self.held,
+            LfoShape::SmoothRandom => {
+                // Cosine interpolation between the random values
+                let t = 0.5 - 0.5 * (self.phase * core::f32::consts::PI).cos();
+                self.held + (self.next_held - self.held) * t
+            }
+        }
+    }
+}
+
+impl Oscillator for Lfo {
+    fn next_sample(&mut self) -> f32 {
+        let out = self.evaluate();
+
+        if !self.finished {
+            self.phase += self.increment;
+            if self.phase >= 1.0 {
+                if self.one_shot {
+                    self.phase = 1.0;
+                    self.finished = true;
+                } else {
+                    self.phase -= self.phase.floor();
+                    self.held = self.next_held;
+                    self.next_held = self.rng.next_bipolar();
+                }
+            }
+        }
+
+        match self.polarity {
+            Polarity::Bipolar => out,
+            Polarity::Unipolar => (out + 1.0) * 0.5,
+        }
+    }
+
+    /// Restart the cycle and reseed the random shapes.
+    fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.phase = 0.0;
+        self.finished = false;
+        self.held = self.rng.next_bipolar();
+        self.next_held = self.rng.next_bipolar();
+    }
+
+    fn frequency(&self) -> f32 {
+        self.rate.hertz()
+    }
+
+    /// Set the rate in hertz, replacing any tempo sync.
+    fn set_frequency(&mut self, freq: f32) {
+        self.set_rate(LfoRate::Hertz(freq));
+    }
+
+    /// Set the phase within the cycle, before the phase offset. A finished
+    /// one-shot LFO continues from the new phase.
+    fn set_phase(&mut self, phase: f32) {
+        self.phase = phase - phase.floor();
+        self.finished = false;
+    }
+}
+
+/// Phase increment per sample of a rate, limited to one cycle per sample. A
+/// rate which isn't a number stops the LFO instead of corrupting its phase.
+fn increment(rate: LfoRate, sample_rate: f32) -> f32 {
+    let increment = rate.hertz().abs() / sample_rate;
+    if increment.is_nan() {
+        0.0
+    } else {
+        increment.min(1.0)
+    }
+}
+
+/// Create a unipolar LFO for sweeping an effect parameter, starting
+/// `phase_offset` cycles into the waveform.
+pub(crate) fn sweep_lfo(
+    shape: LfoShape,
+    rate: LfoRate,
+    phase_offset: f32,
+    sample_rate: f32,
+) -> Lfo {
+    let mut lfo = Lfo::new(shape, rate, sample_rate);
+    lfo.set_polarity(Polarity::Unipolar);
+    lfo.set_phase_offset(phase_offset);
+    lfo
+}
@@ -1,7 +1,26 @@
+pub mod additive;
+pub mod chorus;
+pub mod delay;
+pub mod distortion;
+pub mod effect;
+pub mod envelope;
+mod error;
+pub mod filter;
+pub mod fixed;
+pub mod fm;
+pub mod lfo;
+pub mod modulation;
+pub mod noise;
 pub mod oscillator;
+pub mod phaser;
+pub mod reverb;
+pub mod sine;
+pub mod unison;
 mod wave;
+pub mod wavetable;
 
-pub use wave::MinimalWaveHeader;
+pub use error::Error;
+pub use wave::{ChannelLayout, MinimalWaveHeader, WaveData, WaveWriter};
 
 /// Calculate the frequency of a supplied note. An input of (3, 3) corresponds
 /// to C4.
@@ -24,11 +43,25 @@ pub fn note_frequency(octave: f32, note: f32) -> f32 {
     440.0 * 2.0f32.powf(((octave - 4.0) * 12.0 + note) / 12.0)
 }
 
+/// Calculate the length in seconds of a number of beats at a tempo in beats
+/// per minute. A beat is a quarter note, so 0.5 beats is an eighth note and
+/// 1/3 of a beat is an eighth note triplet.
+pub fn beats_to_seconds(beats: f32, bpm: f32) -> f32 {
+    beats * 60.0 / bpm
+}
+
 /// Trait for writing WAVE files using the implementing type's bit depth.
 pub trait WaveComponent {
+    /// Format tag written to the `fmt ` chunk, 1 for PCM and 3 for IEEE float.
+    const AUDIO_FORMAT: u16 = 1;
+
     fn float_to_audio_sample(float: f32) -> Vec<u8>;
 }
 
+/// Packed 24-bit signed integer for writing 24-bit PCM WAVE files.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct I24([u8; 3]);
+
 impl WaveComponent for u8 {
     fn float_to_audio_sample(float: f32) -> Vec<u8> {
         vec![f32::clamp_component((float + 1.0) * 127.5, 0.0, 255.0).round() as u8]
@@ -63,6 +96,36 @@ impl WaveComponent for i32 {
     }
 }
 
+impl WaveComponent for I24 {
+    fn float_to_audio_sample(float: f32) -> Vec<u8> {
+        let [byte_0, byte_1, byte_2, _] = (f64::clamp_component(
+            f64::from(float) * 8_388_607.0,
+            -8_388_608.0,
+            8_388_607.0,
+        )
+        .round() as i32)
+            .to_le_bytes();
+
+        vec![byte_0, byte_1, byte_2]
+    }
+}
+
+impl WaveComponent for f32 {
+    const AUDIO_FORMAT: u16 = 3;
+
+    fn float_to_audio_sample(float: f32) -> Vec<u8> {
+        float.to_le_bytes().to_vec()
+    }
+}
+
+impl WaveComponent for f64 {
+    const AUDIO_FORMAT: u16 = 3;
+
+    fn float_to_audio_sample(float: f32) -> Vec<u8> {
+        f64::from(float).to_le_bytes().to_vec()
+    }
+}
+
 /// Trait for enabling clamping on float components.
 pub trait FloatComponent {
     fn clamp_component(n: Self, min: Self, max: Self) -> Self;
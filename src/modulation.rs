@@ -0,0 +1,240 @@
+use crate::oscillator::Oscillator;
+
+/// Hard sync, where the slave oscillator's phase is reset at the start of every
+/// cycle of the master oscillator. Resets happen at the exact sub-sample time
+/// of the master's cycle and the resulting step is smoothed with a polynomial
+/// band-limited step, which delays the output by one sample.
+///
+/// The pitch of the output follows the master while sweeping the slave's
+/// frequency changes the timbre. Resets are timed from the master's frequency,
+/// assuming both oscillators start at phase 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HardSync<M: Oscillator, S: Oscillator + Clone> {
+    master: M,
+    slave: S,
+    sample_rate: f32,
+    master_phase: f32,
+    // Slave output from the previous sample, waiting for step correction
+    delayed: f32,
+    master_out: f32,
+}
+
+impl<M: Oscillator, S: Oscillator + Clone> HardSync<M, S> {
+    /// Create a new hard sync pair. Both oscillators are reset so their
+    /// phases line up.
+    pub fn new(mut master: M, mut slave: S, sample_rate: f32) -> Self {
+        master.reset();
+        slave.reset();
+        Self {
+            master,
+            slave,
+            sample_rate,
+            master_phase: 0.0,
+            delayed: 0.0,
+            master_out: 0.0,
+        }
+    }
+
+    /// Master oscillator, which sets the pitch.
+    pub fn master(&self) -> &M {
+        &self.master
+    }
+
+    /// Mutable access to the master oscillator.
+    pub fn master_mut(&mut self) -> &mut M {
+        &mut self.master
+    }
+
+    /// Slave oscillator, which is reset by the master.
+    pub fn slave(&self) -> &S {
+        &self.slave
+    }
+
+    /// Mutable access to the slave oscillator, e.g. to sweep its frequency.
+    pub fn slave_mut(&mut self) -> &mut S {
+        &mut self.slave
+    }
+
+    /// Output of the master oscillator for the most recent sample.
+    pub fn master_output(&self) -> f32 {
+        self.master_out
+    }
+}
+
+impl<M: Oscillator, S: Oscillator + Clone> Oscillator for HardSync<M, S> {
+    fn next_sample(&mut self) -> f32 {
+        self.master_out = self.master.next_sample();
+        let increment = (self.master.frequency() / self.sample_rate).abs();
+        self.master_phase += increment;
+
+        let out = if self.master_phase >= 1.0 && increment > 0.0 {
+            self.master_phase -= self.master_phase.floor();
+            // Samples elapsed since the master's cycle restarted
+            let since = (self.master_phase / increment).min(1.0);
+
+            let unsynced = self.slave.clone().next_sample();
+            let freq = self.slave.frequency();
+            self.slave.reset();
+            self.slave.set_frequency(freq * since);
+            let synced = self.slave.next_sample();
+            self.slave.set_frequency(freq);
+
+            // Spread the step over the samples either side of it
+            let step = synced - unsynced;
+            self.delayed += step * since * since * 0.5;
+            synced - step * (1.0 - since) * (1.0 - since) * 0.5
+        } else {
+            self.slave.next_sample()
+        };
+
+        core::mem::replace(&mut self.delayed, out)
+    }
+
+    fn reset(&mut self) {
+        self.master.reset();
+        self.slave.reset();
+        self.master_phase = 0.0;
+        self.delayed = 0.0;
+        self.master_out = 0.0;
+    }
+
+    fn frequency(&self) -> f32 {
+        self.master.frequency()
+    }
+
+    /// Set the master's frequency, which sets the pitch.
+    fn set_frequency(&mut self, freq: f32) {
+        self.master.set_frequency(freq);
+    }
+
+    /// Set the master's phase and move the slave to where it would be that
+    /// far into the master's cycle.
+    fn set_phase(&mut self, phase: f32) {
+        let phase = phase - phase.floor();
+        let master_freq = self.master.frequency();
+        let ratio = if master_freq == 0.0 {
+            0.0
+        } else {
+            self.slave.frequency() / master_freq
+        };
+        self.master.set_phase(phase);
+        self.slave.set_phase(phase * ratio.abs());
+        self.master_phase = phase;
+        self.delayed = 0.0;
+    }
+}
+
+/// Ring modulation, the product of two oscillators, giving the sum and
+/// difference of their frequencies for metallic and bell-like tones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RingMod<C: Oscillator, M: Oscillator> {
+    carrier: C,
+    modulator: M,
+}
+
+impl<C: Oscillator, M: Oscillator> RingMod<C, M> {
+    /// Create a new ring modulator.
+    pub fn new(carrier: C, modulator: M) -> Self {
+        Self { carrier, modulator }
+    }
+
+    /// Mutable access to the carrier oscillator.
+    pub fn carrier_mut(&mut self) -> &mut C {
+        &mut self.carrier
+    }
+
+    /// Mutable access to the modulating oscillator.
+    pub fn modulator_mut(&mut self) -> &mut M {
+        &mut self.modulator
+    }
+}
+
+impl<C: Oscillator, M: Oscillator> Oscillator for RingMod<C, M> {
+    fn next_sample(&mut self) -> f32 {
+        self.carrier.next_sample() * self.modulator.next_sample()
+    }
+
+    fn reset(&mut self) {
+        self.carrier.reset();
+        self.modulator.reset();
+    }
+
+    fn frequency(&self) -> f32 {
+        self.carrier.frequency()
+    }
+
+    /// Set the carrier's frequency.
+    fn set_frequency(&mut self, freq: f32) {
+        self.carrier.set_frequency(freq);
+    }
+
+    /// Set the phase of both the carrier and the modulator.
+    fn set_phase(&mut self, phase: f32) {
+        self.carrier.set_phase(phase);
+        self.modulator.set_phase(phase);
+    }
+}
+
+/// Amplitude modulation, scaling a carrier by a modulator shifted to range
+/// from 0.0 to 1.0. A depth of 0.0 leaves the carrier unchanged and a depth of
+/// 1.0 fully modulates it, keeping the carrier frequency in the output unlike
+/// ring modulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmplitudeMod<C: Oscillator, M: Oscillator> {
+    carrier: C,
+    modulator: M,
+    depth: f32,
+}
+
+impl<C: Oscillator, M: Oscillator> AmplitudeMod<C, M> {
+    /// Create a new amplitude modulator with a depth from 0.0 to 1.0.
+    pub fn new(carrier: C, modulator: M, depth: f32) -> Self {
+        Self {
+            carrier,
+            modulator,
+            depth,
+        }
+    }
+
+    /// Set the modulation depth from 0.0 to 1.0.
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth;
+    }
+
+    /// Mutable access to the carrier oscillator.
+    pub fn carrier_mut(&mut self) -> &mut C {
+        &mut self.carrier
+    }
+
+    /// Mutable access to the modulating oscillator.
+    pub fn modulator_mut(&mut self) -> &mut M {
+        &mut self.modulator
+    }
+}
+
+impl<C: Oscillator, M: Oscillator> Oscillator for AmplitudeMod<C, M> {
+    fn next_sample(&mut self) -> f32 {
+        let gain = 1.0 - self.depth * (1.0 - self.modulator.next_sample()) * 0.5;
+        self.carrier.next_sample() * gain
+    }
+
+    fn reset(&mut self) {
+        self.carrier.reset();
+        self.modulator.reset();
+    }
+
+    fn frequency(&self) -> f32 {
+        self.carrier.frequency()
+    }
+
+    /// Set the carrier's frequency.
+    fn set_frequency(&mut self, freq: f32) {
+        self.carrier.set_frequency(freq);
+    }
+
+    /// Set the phase of both the carrier and the modulator.
+    fn set_phase(&mut self, phase: f32) {
+        self.carrier.set_phase(phase);
+        self.modulator.set_phase(phase);
+    }
+}
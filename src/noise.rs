@@ -0,0 +1,195 @@
+/// Small deterministic pseudorandom number generator (PCG-XSH-RR 64/32). The
+/// same seed always produces the same sequence on every platform.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+    const INCREMENT: u64 = 1_442_695_040_888_963_407;
+
+    /// Create a new generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Next uniformly distributed `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state
+            .wrapping_mul(Self::MULTIPLIER)
+            .wrapping_add(Self::INCREMENT);
+
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    /// Next uniformly distributed `f32` ranging from 0.0 to 1.0, exclusive.
+    pub fn next_f32(&mut self) -> f32 {
+        // Use the top 24 bits so every value is exactly representable
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Next uniformly distributed `f32` ranging from -1.0 to 1.0, exclusive.
+    pub fn next_bipolar(&mut self) -> f32 {
+        self.next_f32() * 2.0 - 1.0
+    }
+}
+
+/// Trait for noise generators, which produce output without a frequency.
+pub trait NoiseSource {
+    /// Generate the next output sample.
+    fn next_sample(&mut self) -> f32;
+
+    /// Reseed with the original seed and clear any filter state, so the same
+    /// sequence is generated again.
+    fn reset(&mut self);
+
+    /// Fill a buffer with consecutive output samples.
+    fn process_block(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.next_sample();
+        }
+    }
+}
+
+/// White noise with equal power at all frequencies, ranging from -1.0 to 1.0.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WhiteNoise {
+    seed: u64,
+    rng: Rng,
+}
+
+impl WhiteNoise {
+    /// Create a new white noise generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl NoiseSource for WhiteNoise {
+    fn next_sample(&mut self) -> f32 {
+        self.rng.next_bipolar()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.seed);
+    }
+}
+
+/// Pink noise falling 3 dB per octave, made by filtering white noise with Paul
+/// Kellet's refined filter. The filter is tuned for a 44.1 kHz sample rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PinkNoise {
+    seed: u64,
+    rng: Rng,
+    b: [f32; 7],
+}
+
+impl PinkNoise {
+    /// Create a new pink noise generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+            b: [0.0; 7],
+        }
+    }
+}
+
+impl NoiseSource for PinkNoise {
+    fn next_sample(&mut self) -> f32 {
+        let white = self.rng.next_bipolar();
+        let b = &mut self.b;
+        b[0] = 0.99886 * b[0] + white * 0.055_517_9;
+        b[1] = 0.99332 * b[1] + white * 0.075_075_9;
+        b[2] = 0.96900 * b[2] + white * 0.153_852;
+        b[3] = 0.86650 * b[3] + white * 0.310_485_6;
+        b[4] = 0.55000 * b[4] + white * 0.532_952_2;
+        b[5] = -0.7616 * b[5] - white * 0.016_898;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115_926;
+
+        // Scale to roughly the same range as the white noise input
+        pink * 0.11
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.seed);
+    }
+}
+
+/// Brown (red) noise falling 6 dB per octave, made by integrating white noise
+/// with a leaky integrator to keep it from drifting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BrownNoise {
+    seed: u64,
+    rng: Rng,
+    last: f32,
+}
+
+impl BrownNoise {
+    /// Create a new brown noise generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+            last: 0.0,
+        }
+    }
+}
+
+impl NoiseSource for BrownNoise {
+    fn next_sample(&mut self) -> f32 {
+        let white = self.rng.next_bipolar();
+        self.last = (self.last + 0.02 * white) / 1.02;
+
+        // Scale to roughly the same range as the white noise input
+        self.last * 3.5
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.seed);
+    }
+}
+
+/// Blue noise rising 3 dB per octave, made by differentiating pink noise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlueNoise {
+    pink: PinkNoise,
+    last: f32,
+}
+
+impl BlueNoise {
+    /// Create a new blue noise generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            pink: PinkNoise::new(seed),
+            last: 0.0,
+        }
+    }
+}
+
+impl NoiseSource for BlueNoise {
+    fn next_sample(&mut self) -> f32 {
+        let pink = self.pink.next_sample();
+        let blue = pink - self.last;
+        self.last = pink;
+
+        // Scale to roughly the same range as the white noise input
+        blue * 3.0
+    }
+
+    fn reset(&mut self) {
+        self.pink.reset();
+        self.last = 0.0;
+    }
+}
@@ -1,5 +1,28 @@
+use crate::{
+    sine::{DefaultSine, Sine},
+    FloatComponent,
+};
+
 /// Sine wave oscillator.
 pub fn advance_sine_osc(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    advance_sine_osc_with::<DefaultSine>(phase, freq, sample_rate)
+}
+
+/// Sine wave oscillator using the sine implementation `S`.
+pub fn advance_sine_osc_with<S: Sine>(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    advance_radian_phase(phase, freq, sample_rate);
+    S::sin(*phase)
+}
+
+/// Sine wave oscillator with a phase modulation input in radians, which is
+/// added to the phase when calculating the output but doesn't accumulate.
+pub fn advance_sine_osc_pm(phase: &mut f32, freq: f32, sample_rate: f32, modulation: f32) -> f32 {
+    advance_radian_phase(phase, freq, sample_rate);
+    DefaultSine::sin(*phase + modulation)
+}
+
+/// Advance a phase ranging from 0.0 to `TAU` radians.
+pub(crate) fn advance_radian_phase(phase: &mut f32, freq: f32, sample_rate: f32) {
     *phase += core::f32::consts::TAU * freq / sample_rate;
     while *phase >= core::f32::consts::TAU {
         *phase -= core::f32::consts::TAU;
@@ -7,7 +30,6 @@ pub fn advance_sine_osc(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
     while *phase < 0.0 {
         *phase += core::f32::consts::TAU;
     }
-    phase.sin()
 }
 
 /// Square wave oscillator.
@@ -19,11 +41,7 @@ pub fn advance_square_osc(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
     while *phase < 0.0 {
         *phase += 1.0;
     }
-    if *phase <= 0.5 {
-        1.0
-    } else {
-        -1.0
-    }
+    square_wave(*phase)
 }
 
 /// Saw wave oscillator.
@@ -35,7 +53,7 @@ pub fn advance_saw_osc(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
     while *phase < 0.0 {
         *phase += 1.0;
     }
-    -((*phase * 2.0) - 1.0)
+    saw_wave(*phase)
 }
 
 /// Triangle wave oscillator.
@@ -47,111 +65,649 @@ pub fn advance_triangle_osc(phase: &mut f32, freq: f32, sample_rate: f32) -> f32
     while *phase < 0.0 {
         *phase += 1.0;
     }
-    if *phase <= 0.5 {
-        (*phase * 4.0) - 1.0
-    } else {
-        ((1.0 - *phase) * 4.0) - 1.0
-    }
+    triangle_wave(*phase)
 }
 
-/// Band-limited saw wave oscillator. If `harmonics` is 0, the maximum number of
-/// harmonics under the Nyquist frequency will be used.
-pub fn advance_saw_osc_bl(
-    phase: &mut f32,
-    mut freq: f32,
-    sample_rate: f32,
-    mut harmonics: i8,
-) -> f32 {
-    *phase += core::f32::consts::TAU * freq / sample_rate;
-    while *phase >= core::f32::consts::TAU {
-        *phase -= core::f32::consts::TAU;
+/// Pulse wave oscillator. `width` is the fraction of the cycle spent high,
+/// ranging from 0.0 to 1.0, where 0.5 is a square wave.
+pub fn advance_pulse_osc(phase: &mut f32, freq: f32, sample_rate: f32, width: f32) -> f32 {
+    *phase += freq / sample_rate;
+    while *phase > 1.0 {
+        *phase -= 1.0;
     }
     while *phase < 0.0 {
-        *phase += core::f32::consts::TAU;
+        *phase += 1.0;
     }
+    if *phase < f32::clamp_component(width, 0.0, 1.0) {
+        1.0
+    } else {
+        -1.0
+    }
+}
 
-    // if harmonics is zero, calculate max harmonics before hitting nyquist freq
-    if harmonics == 0 && freq != 0.0 {
-        while freq < sample_rate * 0.5 {
-            harmonics += 1;
-            freq *= 2.0;
-        }
+/// Square wave at phase `t` from 0.0 to 1.0.
+pub(crate) fn square_wave(t: f32) -> f32 {
+    if t <= 0.5 {
+        1.0
+    } else {
+        -1.0
     }
+}
 
-    let mut ret = 0.0;
-    for i in 1..=harmonics as usize {
-        ret += (*phase * i as f32).sin() / i as f32;
+/// Saw wave at phase `t` from 0.0 to 1.0.
+pub(crate) fn saw_wave(t: f32) -> f32 {
+    -((t * 2.0) - 1.0)
+}
+
+/// Triangle wave at phase `t` from 0.0 to 1.0.
+pub(crate) fn triangle_wave(t: f32) -> f32 {
+    if t <= 0.5 {
+        (t * 4.0) - 1.0
+    } else {
+        ((1.0 - t) * 4.0) - 1.0
     }
+}
 
-    ret * 2.0 / core::f32::consts::PI
+/// Band-limited saw wave oscillator. If `harmonics` is 0, one harmonic is used
+/// for every octave under the Nyquist frequency.
+pub fn advance_saw_osc_bl(phase: &mut f32, freq: f32, sample_rate: f32, harmonics: i8) -> f32 {
+    advance_saw_osc_bl_with::<DefaultSine>(phase, freq, sample_rate, harmonics)
+}
+
+/// Band-limited saw wave oscillator using the sine implementation `S`.
+pub fn advance_saw_osc_bl_with<S: Sine>(
+    phase: &mut f32,
+    freq: f32,
+    sample_rate: f32,
+    harmonics: i8,
+) -> f32 {
+    advance_radian_phase(phase, freq, sample_rate);
+    let harmonics = if harmonics == 0 {
+        octave_harmonics(freq / sample_rate)
+    } else {
+        harmonics
+    };
+    saw_bl::<S>(*phase, harmonics)
 }
 
 /// Band-limited square wave oscillator. If `harmonics` is 0, the maximum
-/// number of harmonics under the Nyquist frequency will be used.
-pub fn advance_square_osc_bl(
+/// number of harmonics under the Nyquist frequency will be used, up to
+/// `i8::MAX`.
+pub fn advance_square_osc_bl(phase: &mut f32, freq: f32, sample_rate: f32, harmonics: i8) -> f32 {
+    advance_square_osc_bl_with::<DefaultSine>(phase, freq, sample_rate, harmonics)
+}
+
+/// Band-limited square wave oscillator using the sine implementation `S`.
+pub fn advance_square_osc_bl_with<S: Sine>(
     phase: &mut f32,
     freq: f32,
     sample_rate: f32,
-    mut harmonics: i8,
+    harmonics: i8,
 ) -> f32 {
-    *phase += core::f32::consts::TAU * freq / sample_rate;
-    while *phase >= core::f32::consts::TAU {
-        *phase -= core::f32::consts::TAU;
-    }
-    while *phase < 0.0 {
-        *phase += core::f32::consts::TAU;
-    }
+    advance_radian_phase(phase, freq, sample_rate);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(freq / sample_rate, 2.0)
+    } else {
+        harmonics
+    };
+    square_bl::<S>(*phase, harmonics)
+}
 
-    // if harmonics is zero, calculate max harmonics before hitting nyquist freq
-    if harmonics == 0 && freq != 0.0 {
-        while freq * f32::from(harmonics * 2 - 1) < sample_rate * 0.5 {
-            harmonics += 1;
-        }
-        harmonics -= 1;
-    }
+/// Band-limited triangle wave oscillator. If `harmonics` is 0, the maximum
+/// number of harmonics under the Nyquist frequency will be used, up to
+/// `i8::MAX`.
+pub fn advance_triangle_osc_bl(phase: &mut f32, freq: f32, sample_rate: f32, harmonics: i8) -> f32 {
+    advance_triangle_osc_bl_with::<DefaultSine>(phase, freq, sample_rate, harmonics)
+}
 
-    let mut ret = 0.0;
-    for i in 1..=harmonics as usize {
-        ret += (*phase * (i * 2 - 1) as f32).sin() / (i * 2 - 1) as f32;
-    }
+/// Band-limited triangle wave oscillator using the sine implementation `S`.
+pub fn advance_triangle_osc_bl_with<S: Sine>(
+    phase: &mut f32,
+    freq: f32,
+    sample_rate: f32,
+    harmonics: i8,
+) -> f32 {
+    advance_radian_phase(phase, freq, sample_rate);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(freq / sample_rate, 2.0)
+    } else {
+        harmonics
+    };
+    triangle_bl::<S>(*phase, harmonics)
+}
 
-    ret * 4.0 / core::f32::consts::PI
+/// Band-limited pulse wave oscillator. `width` is the fraction of the cycle
+/// spent high, ranging from 0.0 to 1.0, and can change every sample without
+/// clicks. The output includes the DC offset of `2.0 * width - 1.0`, like
+/// `advance_pulse_osc`. If `harmonics` is 0, the maximum number of harmonics
+/// under the Nyquist frequency will be used, up to `i8::MAX`.
+pub fn advance_pulse_osc_bl(
+    phase: &mut f32,
+    freq: f32,
+    sample_rate: f32,
+    width: f32,
+    harmonics: i8,
+) -> f32 {
+    advance_pulse_osc_bl_with::<DefaultSine>(phase, freq, sample_rate, width, harmonics)
 }
 
-/// Band-limited triangle wave oscillator. If `harmonics` is 0, the maximum
-/// number of harmonics under the Nyquist frequency will be used.
-pub fn advance_triangle_osc_bl(
+/// Band-limited pulse wave oscillator using the sine implementation `S`.
+pub fn advance_pulse_osc_bl_with<S: Sine>(
     phase: &mut f32,
     freq: f32,
     sample_rate: f32,
-    mut harmonics: i8,
+    width: f32,
+    harmonics: i8,
 ) -> f32 {
-    *phase += core::f32::consts::TAU * freq / sample_rate;
-    while *phase >= core::f32::consts::TAU {
-        *phase -= core::f32::consts::TAU;
-    }
-    while *phase < 0.0 {
-        *phase += core::f32::consts::TAU;
-    }
+    advance_radian_phase(phase, freq, sample_rate);
+    let harmonics = if harmonics == 0 {
+        max_harmonics(freq / sample_rate, 1.0)
+    } else {
+        harmonics
+    };
+    pulse_bl::<S>(*phase, width, harmonics)
+}
 
-    // if harmonics is zero, calculate max harmonics before hitting nyquist freq
-    if harmonics == 0 && freq != 0.0 {
-        while freq * f32::from(harmonics * 2 - 1) < sample_rate * 0.5 {
+/// Number of harmonics used by the band-limited saw when `harmonics` is 0, one
+/// for every octave under the Nyquist frequency. `dt` is the frequency as a
+/// fraction of the sample rate.
+pub(crate) fn octave_harmonics(dt: f32) -> i8 {
+    let mut dt = dt.abs();
+    let mut harmonics = 0;
+    if dt != 0.0 {
+        while dt < 0.5 && harmonics < i8::MAX {
             harmonics += 1;
+            dt *= 2.0;
         }
-        harmonics -= 1;
+    }
+    harmonics
+}
+
+/// Number of harmonics under the Nyquist frequency, up to `i8::MAX`, where
+/// every `step` harmonics are used starting at the fundamental. `dt` is the
+/// frequency as a fraction of the sample rate.
+pub(crate) fn max_harmonics(dt: f32, step: f32) -> i8 {
+    let dt = dt.abs();
+    if dt == 0.0 {
+        return 0;
+    }
+    let harmonics = ((0.5 / dt + step - 1.0) / step).ceil() - 1.0;
+    harmonics.min(f32::from(i8::MAX)) as i8
+}
+
+/// Band-limited saw wave at `phase` from 0.0 to `TAU` radians, summing
+/// `harmonics` harmonics.
+pub(crate) fn saw_bl<S: Sine>(phase: f32, harmonics: i8) -> f32 {
+    let mut ret = 0.0;
+    for i in 1..=harmonics.max(0) as usize {
+        ret += S::sin(phase * i as f32) / i as f32;
+    }
+
+    ret * 2.0 / core::f32::consts::PI
+}
+
+/// Band-limited square wave at `phase` from 0.0 to `TAU` radians, summing
+/// `harmonics` odd harmonics.
+pub(crate) fn square_bl<S: Sine>(phase: f32, harmonics: i8) -> f32 {
+    let mut ret = 0.0;
+    for i in 1..=harmonics.max(0) as usize {
+        ret += S::sin(phase * (i * 2 - 1) as f32) / (i * 2 - 1) as f32;
     }
 
+    ret * 4.0 / core::f32::consts::PI
+}
+
+/// Band-limited triangle wave at `phase` from 0.0 to `TAU` radians, summing
+/// `harmonics` odd harmonics.
+pub(crate) fn triangle_bl<S: Sine>(phase: f32, harmonics: i8) -> f32 {
     let mut subtract = true;
     let mut ret = 0.0;
-    for i in 1..=harmonics as usize {
+    for i in 1..=harmonics.max(0) as usize {
         if subtract {
-            ret -= (*phase * (i * 2 - 1) as f32).sin() / (i * 2 - 1).pow(2) as f32;
+            ret -= S::sin(phase * (i * 2 - 1) as f32) / (i * 2 - 1).pow(2) as f32;
         } else {
-            ret += (*phase * (i * 2 - 1) as f32).sin() / (i * 2 - 1).pow(2) as f32;
+            ret += S::sin(phase * (i * 2 - 1) as f32) / (i * 2 - 1).pow(2) as f32;
         }
         subtract = !subtract;
     }
 
     ret * 8.0 / core::f32::consts::PI.powi(2)
 }
+
+/// Band-limited pulse wave at `phase` from 0.0 to `TAU` radians, summing
+/// `harmonics` harmonics.
+pub(crate) fn pulse_bl<S: Sine>(phase: f32, width: f32, harmonics: i8) -> f32 {
+    // Each harmonic is a cosine centered on the middle of the high section
+    let width = f32::clamp_component(width, 0.0, 1.0);
+    let center = phase - core::f32::consts::PI * width;
+    let mut ret = 0.0;
+    for i in 1..=harmonics.max(0) as usize {
+        let i = i as f32;
+        ret += S::sin(core::f32::consts::PI * width * i) * S::cos(center * i) / i;
+    }
+
+    ret * 4.0 / core::f32::consts::PI + 2.0 * width - 1.0
+}
+
+/// Polynomial approximation of the band-limited step residual for a step of
+/// 2. `t` is the phase from 0.0 to 1.0 and `dt` is the phase increment per
+/// sample.
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = t / dt;
+        t + t - t * t - 1.0
+    } else if t > 1.0 - dt {
+        let t = (t - 1.0) / dt;
+        t * t + t + t + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Polynomial approximation of the band-limited ramp residual, the integral of
+/// `poly_blep`, used to smooth discontinuities in slope.
+fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if t < dt {
+        let t = t / dt - 1.0;
+        -t * t * t / 3.0
+    } else if t > 1.0 - dt {
+        let t = (t - 1.0) / dt + 1.0;
+        t * t * t / 3.0
+    } else {
+        0.0
+    }
+}
+
+/// Advance a phase ranging from 0.0 to 1.0, returning the phase increment.
+pub(crate) fn advance_unit_phase(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    let dt = freq / sample_rate;
+    *phase += dt;
+    while *phase >= 1.0 {
+        *phase -= 1.0;
+    }
+    while *phase < 0.0 {
+        *phase += 1.0;
+    }
+    dt.abs()
+}
+
+/// PolyBLEP saw wave oscillator. Runs in constant time per sample with far
+/// less aliasing than `advance_saw_osc`.
+pub fn advance_saw_osc_polyblep(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    let dt = advance_unit_phase(phase, freq, sample_rate);
+    saw_polyblep(*phase, dt)
+}
+
+/// PolyBLEP square wave oscillator. Runs in constant time per sample with far
+/// less aliasing than `advance_square_osc`.
+pub fn advance_square_osc_polyblep(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    advance_pulse_osc_polyblep(phase, freq, sample_rate, 0.5)
+}
+
+/// PolyBLEP pulse wave oscillator. `width` is the fraction of the cycle spent
+/// high, ranging from 0.0 to 1.0, where 0.5 is a square wave.
+pub fn advance_pulse_osc_polyblep(phase: &mut f32, freq: f32, sample_rate: f32, width: f32) -> f32 {
+    let dt = advance_unit_phase(phase, freq, sample_rate);
+    pulse_polyblep(*phase, dt, width)
+}
+
+/// PolyBLAMP triangle wave oscillator. Runs in constant time per sample with
+/// far less aliasing than `advance_triangle_osc`.
+pub fn advance_triangle_osc_polyblamp(phase: &mut f32, freq: f32, sample_rate: f32) -> f32 {
+    let dt = advance_unit_phase(phase, freq, sample_rate);
+    triangle_polyblamp(*phase, dt)
+}
+
+/// PolyBLEP saw wave at phase `t` from 0.0 to 1.0 with a phase increment of
+/// `dt` per sample.
+pub(crate) fn saw_polyblep(t: f32, dt: f32) -> f32 {
+    saw_wave(t) + poly_blep(t, dt)
+}
+
+/// PolyBLEP pulse wave at phase `t` from 0.0 to 1.0 with a phase increment of
+/// `dt` per sample.
+pub(crate) fn pulse_polyblep(t: f32, dt: f32, width: f32) -> f32 {
+    let width = f32::clamp_component(width, 0.0, 1.0);
+
+    let mut falling = t - width;
+    if falling < 0.0 {
+        falling += 1.0;
+    }
+    let naive = if t < width { 1.0 } else { -1.0 };
+
+    naive + poly_blep(t, dt) - poly_blep(falling, dt)
+}
+
+/// PolyBLAMP triangle wave at phase `t` from 0.0 to 1.0 with a phase increment
+/// of `dt` per sample.
+pub(crate) fn triangle_polyblamp(t: f32, dt: f32) -> f32 {
+    let mut peak = t - 0.5;
+    if peak < 0.0 {
+        peak += 1.0;
+    }
+
+    // The slope changes by 8 per cycle at each corner and the residual is
+    // scaled for a change of 2
+    triangle_wave(t) + 4.0 * dt * (poly_blamp(t, dt) - poly_blamp(peak, dt))
+}
+
+/// Trait for oscillators which own their phase, frequency, and sample rate.
+pub trait Oscillator {
+    /// Advance the oscillator by one sample and return the output.
+    fn next_sample(&mut self) -> f32;
+
+    /// Reset the phase to the start of the cycle.
+    fn reset(&mut self);
+
+    /// Frequency in hertz.
+    fn frequency(&self) -> f32;
+
+    /// Set the frequency in hertz, keeping the current phase.
+    fn set_frequency(&mut self, freq: f32);
+
+    /// Set the phase as a fraction of a cycle from 0.0 to 1.0. FM voices,
+    /// which don't have a single phase, ignore this.
+    fn set_phase(&mut self, _phase: f32) {}
+
+    /// Fill a buffer with consecutive output samples.
+    fn process_block(&mut self, block: &mut [f32]) {
+        for sample in block.iter_mut() {
+            *sample = self.next_sample();
+        }
+    }
+}
+
+/// Define an oscillator struct which drives one of the `advance_*` functions,
+/// where `$cycle` is the length of the function's phase cycle.
+macro_rules! oscillator {
+    ($(#[$attr:meta])* $name:ident, $advance:ident, $cycle:expr) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name {
+            phase: f32,
+            freq: f32,
+            sample_rate: f32,
+        }
+
+        impl $name {
+            /// Create a new oscillator starting at phase 0.
+            pub fn new(freq: f32, sample_rate: f32) -> Self {
+                Self {
+                    phase: 0.0,
+                    freq,
+                    sample_rate,
+                }
+            }
+        }
+
+        impl Oscillator for $name {
+            fn next_sample(&mut self) -> f32 {
+                $advance(&mut self.phase, self.freq, self.sample_rate)
+            }
+
+            fn reset(&mut self) {
+                self.phase = 0.0;
+            }
+
+            fn frequency(&self) -> f32 {
+                self.freq
+            }
+
+            fn set_frequency(&mut self, freq: f32) {
+                self.freq = freq;
+            }
+
+            fn set_phase(&mut self, phase: f32) {
+                self.phase = (phase - phase.floor()) * $cycle;
+            }
+        }
+    };
+}
+
+/// Define a band-limited oscillator struct which drives one of the
+/// `advance_*_bl` functions.
+macro_rules! oscillator_bl {
+    ($(#[$attr:meta])* $name:ident, $advance:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name {
+            phase: f32,
+            freq: f32,
+            sample_rate: f32,
+            harmonics: i8,
+        }
+
+        impl $name {
+            /// Create a new oscillator starting at phase 0. If `harmonics` is
+            /// 0, the maximum number of harmonics under the Nyquist frequency
+            /// will be used.
+            pub fn new(freq: f32, sample_rate: f32, harmonics: i8) -> Self {
+                Self {
+                    phase: 0.0,
+                    freq,
+                    sample_rate,
+                    harmonics,
+                }
+            }
+
+            /// Set the number of harmonics, 0 for as many as fit under the
+            /// Nyquist frequency.
+            pub fn set_harmonics(&mut self, harmonics: i8) {
+                self.harmonics = harmonics;
+            }
+        }
+
+        impl Oscillator for $name {
+            fn next_sample(&mut self) -> f32 {
+                $advance(&mut self.phase, self.freq, self.sample_rate, self.harmonics)
+            }
+
+            fn reset(&mut self) {
+                self.phase = 0.0;
+            }
+
+            fn frequency(&self) -> f32 {
+                self.freq
+            }
+
+            fn set_frequency(&mut self, freq: f32) {
+                self.freq = freq;
+            }
+
+            fn set_phase(&mut self, phase: f32) {
+                self.phase = (phase - phase.floor()) * core::f32::consts::TAU;
+            }
+        }
+    };
+}
+
+oscillator!(
+    /// Sine wave oscillator.
+    SineOsc,
+    advance_sine_osc,
+    core::f32::consts::TAU
+);
+oscillator!(
+    /// Square wave oscillator.
+    SquareOsc,
+    advance_square_osc,
+    1.0
+);
+oscillator!(
+    /// Saw wave oscillator.
+    SawOsc,
+    advance_saw_osc,
+    1.0
+);
+oscillator!(
+    /// Triangle wave oscillator.
+    TriangleOsc,
+    advance_triangle_osc,
+    1.0
+);
+oscillator_bl!(
+    /// Band-limited saw wave oscillator.
+    SawOscBl,
+    advance_saw_osc_bl
+);
+oscillator_bl!(
+    /// Band-limited square wave oscillator.
+    SquareOscBl,
+    advance_square_osc_bl
+);
+oscillator_bl!(
+    /// Band-limited triangle wave oscillator.
+    TriangleOscBl,
+    advance_triangle_osc_bl
+);
+oscillator!(
+    /// PolyBLEP saw wave oscillator.
+    SawOscPolyBlep,
+    advance_saw_osc_polyblep,
+    1.0
+);
+oscillator!(
+    /// PolyBLEP square wave oscillator.
+    SquareOscPolyBlep,
+    advance_square_osc_polyblep,
+    1.0
+);
+oscillator!(
+    /// PolyBLAMP triangle wave oscillator.
+    TriangleOscPolyBlamp,
+    advance_triangle_osc_polyblamp,
+    1.0
+);
+
+/// Define a pulse oscillator struct which drives one of the `advance_pulse_*`
+/// functions.
+macro_rules! oscillator_pulse {
+    ($(#[$attr:meta])* $name:ident, $advance:ident) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $name {
+            phase: f32,
+            freq: f32,
+            sample_rate: f32,
+            width: f32,
+        }
+
+        impl $name {
+            /// Create a new oscillator starting at phase 0. `width` is the
+            /// fraction of the cycle spent high, ranging from 0.0 to 1.0.
+            pub fn new(freq: f32, sample_rate: f32, width: f32) -> Self {
+                Self {
+                    phase: 0.0,
+                    freq,
+                    sample_rate,
+                    width,
+                }
+            }
+
+            /// Set the fraction of the cycle spent high. May be called every
+            /// sample to modulate the width.
+            pub fn set_pulse_width(&mut self, width: f32) {
+                self.width = width;
+            }
+        }
+
+        impl Oscillator for $name {
+            fn next_sample(&mut self) -> f32 {
+                $advance(&mut self.phase, self.freq, self.sample_rate, self.width)
+            }
+
+            fn reset(&mut self) {
+                self.phase = 0.0;
+            }
+
+            fn frequency(&self) -> f32 {
+                self.freq
+            }
+
+            fn set_frequency(&mut self, freq: f32) {
+                self.freq = freq;
+            }
+
+            fn set_phase(&mut self, phase: f32) {
+                self.phase = (phase - phase.floor()) * 1.0;
+            }
+        }
+    };
+}
+
+oscillator_pulse!(
+    /// Pulse wave oscillator.
+    PulseOsc,
+    advance_pulse_osc
+);
+oscillator_pulse!(
+    /// PolyBLEP pulse wave oscillator.
+    PulseOscPolyBlep,
+    advance_pulse_osc_polyblep
+);
+
+/// Band-limited pulse wave oscillator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PulseOscBl {
+    phase: f32,
+    freq: f32,
+    sample_rate: f32,
+    width: f32,
+    harmonics: i8,
+}
+
+impl PulseOscBl {
+    /// Create a new oscillator starting at phase 0. `width` is the fraction of
+    /// the cycle spent high, ranging from 0.0 to 1.0. If `harmonics` is 0, the
+    /// maximum number of harmonics under the Nyquist frequency will be used, up
+    /// to `i8::MAX`.
+    pub fn new(freq: f32, sample_rate: f32, width: f32, harmonics: i8) -> Self {
+        Self {
+            phase: 0.0,
+            freq,
+            sample_rate,
+            width,
+            harmonics,
+        }
+    }
+
+    /// Set the fraction of the cycle spent high. May be called every sample to
+    /// modulate the width.
+    pub fn set_pulse_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    /// Set the number of harmonics, 0 for as many as fit under the Nyquist
+    /// frequency.
+    pub fn set_harmonics(&mut self, harmonics: i8) {
+        self.harmonics = harmonics;
+    }
+}
+
+impl Oscillator for PulseOscBl {
+    fn next_sample(&mut self) -> f32 {
+        advance_pulse_osc_bl(
+            &mut self.phase,
+            self.freq,
+            self.sample_rate,
+            self.width,
+            self.harmonics,
+        )
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    fn frequency(&self) -> f32 {
+        self.freq
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq;
+    }
+
+    fn set_phase(&mut self, phase: f32) {
+        self.phase = (phase - phase.floor()) * core::f32::consts::TAU;
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    effect::Effect,
+    lfo::{sweep_lfo, Lfo, LfoRate, LfoShape},
+    oscillator::Oscillator,
+    FloatComponent,
+};
+
+/// Stereo phaser of first order allpass filters in series, with their
+/// cutoff swept exponentially between two frequencies by an LFO. Mixing the
+/// output with the dry signal creates notches which move with the sweep. The
+/// right channel LFO runs a quarter cycle ahead of the left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phaser {
+    stages: [Vec<f32>; 2],
+    lfos: [Lfo; 2],
+    last: [f32; 2],
+    sample_rate: f32,
+    min_freq: f32,
+    max_freq: f32,
+    feedback: f32,
+    mix: f32,
+}
+
+impl Phaser {
+    /// Create a new phaser with a sine LFO at 0.5 Hz sweeping from 200 Hz to
+    /// 4 kHz, a feedback of 0.5, and a mix of 0.5. Each pair of stages adds a
+    /// notch.
+    pub fn new(num_stages: usize, sample_rate: f32) -> Self {
+        let rate = LfoRate::Hertz(0.5);
+        let mut phaser = Self {
+            stages: [vec![0.0; num_stages], vec![0.0; num_stages]],
+            lfos: [
+                sweep_lfo(LfoShape::Sine, rate, 0.0, sample_rate),
+                sweep_lfo(LfoShape::Sine, rate, 0.25, sample_rate),
+            ],
+            last: [0.0; 2],
+            sample_rate,
+            min_freq: 0.0,
+            max_freq: 0.0,
+            feedback: 0.5,
+            mix: 0.5,
+        };
+        phaser.set_range(200.0, 4000.0);
+        phaser
+    }
+
+    /// Set the rate of the LFOs.
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_rate(rate);
+        }
+    }
+
+    /// Set the shape of the LFOs, usually sine or triangle.
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        for lfo in self.lfos.iter_mut() {
+            lfo.set_shape(shape);
+        }
+    }
+
+    /// Set the lowest and highest allpass cutoffs of the sweep in hertz,
+    /// limited to just under the Nyquist frequency.
+    pub fn set_range(&mut self, min_freq: f32, max_freq: f32) {
+        let nyquist = self.sample_rate * 0.49;
+        self.min_freq = f32::clamp_component(min_freq, 1.0, nyquist);
+        self.max_freq = f32::clamp_component(max_freq, 1.0, nyquist);
+    }
+
+    /// Set the amount of the output fed back into the first stage, from -0.99
+    /// to 0.99, sharpening the notches into peaks.
+    pub fn set_feedback(&mut self, feedback: f32) {
+        self.feedback = f32::clamp_component(feedback, -0.99, 0.99);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the allpass output at
+    /// 1.0, where 0.5 gives the deepest notches.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+}
+
+impl Effect for Phaser {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let mut out = [0.0; 2];
+        for (channel, out) in out.iter_mut().enumerate() {
+            let sweep = self.lfos[channel].next_sample();
+            let cutoff = self.min_freq * (self.max_freq / self.min_freq).powf(sweep);
+            let t = (core::f32::consts::PI * cutoff / self.sample_rate).tan();
+            let a = (t - 1.0) / (t + 1.0);
+
+            let mut x = frame[channel] + self.last[channel] * self.feedback;
+            for state in self.stages[channel].iter_mut() {
+                let y = a * x + *state;
+                *state = x - a * y;
+                x = y;
+            }
+            self.last[channel] = x;
+
+            *out = frame[channel] * (1.0 - self.mix) + x * self.mix;
+        }
+        out
+    }
+
+    fn reset(&mut self) {
+        for state in self.stages.iter_mut().flatten() {
+            *state = 0.0;
+        }
+        self.last = [0.0; 2];
+        for lfo in self.lfos.iter_mut() {
+            lfo.reset();
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    delay::{DelayInterpolation, DelayLine},
+    effect::Effect,
+    FloatComponent,
+};
+
+/// Comb filter lengths in samples at 44.1 kHz from Freeverb.
+const COMB_TUNING: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Allpass filter lengths in samples at 44.1 kHz from Freeverb.
+const ALLPASS_TUNING: [usize; 4] = [556, 441, 341, 225];
+/// Extra length of the right channel filters at 44.1 kHz, decorrelating the
+/// channels.
+const STEREO_SPREAD: usize = 23;
+
+/// Gain of the input into the combs, keeping their sum from clipping.
+const INPUT_GAIN: f32 = 0.015;
+/// Gain of the wet output to make up for the input gain.
+const WET_GAIN: f32 = 3.0;
+/// Longest pre-delay in seconds.
+const MAX_PRE_DELAY: f32 = 0.5;
+
+/// Feedback comb filter with a one-pole low pass in the loop.
+#[derive(Clone, Debug, PartialEq)]
+struct Comb {
+    buffer: Vec<f32>,
+    pos: usize,
+    store: f32,
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len],
+            pos: 0,
+            store: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.pos];
+        self.store = output * (1.0 - damping) + self.store * damping;
+        self.buffer[self.pos] = input + self.store * feedback;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// Allpass filter as in Freeverb, with a fixed feedback of 0.5.
+#[derive(Clone, Debug, PartialEq)]
+struct Allpass {
+    buffer: Vec<f32>,
+    pos: usize,
+}
+
+impl Allpass {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len],
+            pos: 0,
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.pos];
+        self.buffer[self.pos] = input + delayed * 0.5;
+        self.pos = (self.pos + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// Freeverb-style stereo reverb of eight parallel comb filters followed by
+/// four series allpass filters per channel. The input is mixed to mono and
+/// the output is deterministic for a given configuration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reverb {
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<Allpass>; 2],
+    pre_delay_lines: [DelayLine; 2],
+    sample_rate: f32,
+    pre_delay: f32,
+    room_size: f32,
+    damping: f32,
+    width: f32,
+    mix: f32,
+}
+
+impl Reverb {
+    /// Create a new reverb with a room size of 0.5, damping of 0.5, no
+    /// pre-delay, full width, and a mix of 0.3.
+    pub fn new(sample_rate: f32) -> Self {
+        // Scale the tunings so the room sounds the same at any sample rate
+        let scale = |len: usize| ((len as f32 * sample_rate / 44100.0) as usize).max(1);
+        let channel = |spread: usize| {
+            (
+                COMB_TUNING
+                    .iter()
+                    .map(|&len| Comb::new(scale(len + spread)))
+                    .collect(),
+                ALLPASS_TUNING
+                    .iter()
+                    .map(|&len| Allpass::new(scale(len + spread)))
+                    .collect(),
+            )
+        };
+        let (left_combs, left_allpasses) = channel(0);
+        let (right_combs, right_allpasses) = channel(STEREO_SPREAD);
+        let pre_delay_line = DelayLine::new(
+            (MAX_PRE_DELAY * sample_rate).ceil() as usize,
+            DelayInterpolation::Linear,
+        );
+
+        Self {
+            combs: [left_combs, right_combs],
+            allpasses: [left_allpasses, right_allpasses],
+            pre_delay_lines: [pre_delay_line.clone(), pre_delay_line],
+            sample_rate,
+            pre_delay: 0.0,
+            room_size: 0.5,
+            damping: 0.5,
+            width: 1.0,
+            mix: 0.3,
+        }
+    }
+
+    /// Set the room size from 0.0 to 1.0, where larger rooms decay longer.
+    pub fn set_room_size(&mut self, room_size: f32) {
+        self.room_size = f32::clamp_component(room_size, 0.0, 1.0);
+    }
+
+    /// Set the damping from 0.0 to 1.0, where higher values make the high
+    /// frequencies decay faster.
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = f32::clamp_component(damping, 0.0, 1.0);
+    }
+
+    /// Set the delay in seconds before the reverb starts, up to 0.5 seconds.
+    pub fn set_pre_delay(&mut self, seconds: f32) {
+        self.pre_delay = f32::clamp_component(seconds, 0.0, MAX_PRE_DELAY) * self.sample_rate;
+    }
+
+    /// Set the stereo width of the reverb from 0.0 for mono to 1.0.
+    pub fn set_width(&mut self, width: f32) {
+        self.width = f32::clamp_component(width, 0.0, 1.0);
+    }
+
+    /// Set the balance between the dry input at 0.0 and the reverb at 1.0.
+    pub fn set_mix(&mut self, mix: f32) {
+        self.mix = f32::clamp_component(mix, 0.0, 1.0);
+    }
+}
+
+impl Effect for Reverb {
+    fn process_frame(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        let feedback = self.room_size * 0.28 + 0.7;
+        let damping = self.damping * 0.4;
+
+        let mut delayed = [0.0; 2];
+        for (i, out) in delayed.iter_mut().enumerate() {
+            let line = &mut self.pre_delay_lines[i];
+            *out = if self.pre_delay >= 1.0 {
+                line.process(frame[i], self.pre_delay)
+            } else {
+                line.write(frame[i]);
+                frame[i]
+            };
+        }
+
+        // Both channels share the mono input and differ in their tunings
+        let input = (delayed[0] + delayed[1]) * INPUT_GAIN;
+        let mut wet = [0.0; 2];
+        for (i, out) in wet.iter_mut().enumerate() {
+            let mut sum = self.combs[i]
+                .iter_mut()
+                .map(|comb| comb.process(input, feedback, damping))
+                .sum::<f32>();
+            for allpass in self.allpasses[i].iter_mut() {
+                sum = allpass.process(sum);
+            }
+            *out = sum * WET_GAIN;
+        }
+
+        // Cross-mix the channels to narrow the width
+        let direct = self.mix * (0.5 + self.width * 0.5);
+        let cross = self.mix * (0.5 - self.width * 0.5);
+        [
+            frame[0] * (1.0 - self.mix) + wet[0] * direct + wet[1] * cross,
+            frame[1] * (1.0 - self.mix) + wet[1] * direct + wet[0] * cross,
+        ]
+    }
+
+    fn reset(&mut self) {
+        for comb in self.combs.iter_mut().flatten() {
+            comb.buffer.iter_mut().for_each(|sample| *sample = 0.0);
+            comb.store = 0.0;
+        }
+        for allpass in self.allpasses.iter_mut().flatten() {
+            allpass.buffer.iter_mut().for_each(|sample| *sample = 0.0);
+        }
+        for line in self.pre_delay_lines.iter_mut() {
+            line.reset();
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    noise::Rng,
+    oscillator::{Oscillator, SawOscPolyBlep},
+};
+
+/// Stack of detuned copies of an oscillator spread across the stereo field,
+/// producing one stereo frame per sample.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unison<O: Oscillator + Clone> {
+    voices: Vec<O>,
+    // Position of each voice from -1.0 to 1.0 across the detune and stereo
+    // spread
+    positions: Vec<f32>,
+    gains: Vec<[f32; 2]>,
+    freq: f32,
+    detune: f32,
+    stereo_spread: f32,
+    random_phase: bool,
+    seed: u64,
+}
+
+impl<O: Oscillator + Clone> Unison<O> {
+    /// Create a unison stack of `num_voices` copies of `oscillator` at its
+    /// current frequency, with no detune or stereo spread and random initial
+    /// phases.
+    ///
+    /// Panics if `num_voices` is 0.
+    pub fn new(oscillator: O, num_voices: usize) -> Self {
+        assert!(num_voices > 0, "unison needs at least one voice");
+        let positions = (0..num_voices)
+            .map(|i| {
+                if num_voices == 1 {
+                    0.0
+                } else {
+                    i as f32 / (num_voices - 1) as f32 * 2.0 - 1.0
+                }
+            })
+            .collect();
+
+        let mut unison = Self {
+            freq: oscillator.frequency(),
+            voices: vec![oscillator; num_voices],
+            positions,
+            gains: vec![[0.0; 2]; num_voices],
+            detune: 0.0,
+            stereo_spread: 0.0,
+            random_phase: true,
+            seed: 0,
+        };
+        unison.update_gains();
+        unison.reset();
+        unison
+    }
+
+    /// Set the detune in cents between the center and the outermost voices.
+    pub fn set_detune(&mut self, detune: f32) {
+        self.detune = detune;
+        self.set_frequency(self.freq);
+    }
+
+    /// Set how far the voices are panned from the center, from 0.0 for mono to
+    /// 1.0 for the outermost voices panned hard left and right.
+    pub fn set_stereo_spread(&mut self, stereo_spread: f32) {
+        self.stereo_spread = stereo_spread;
+        self.update_gains();
+    }
+
+    /// Set whether the voices start at random phases when reset, instead of
+    /// all starting at phase 0. Voices which ignore `Oscillator::set_phase`
+    /// always start from their reset state.
+    pub fn set_random_phase(&mut self, random_phase: bool) {
+        self.random_phase = random_phase;
+    }
+
+    /// Set the seed used for the random initial phases.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Frequency of the center voice in hertz.
+    pub fn frequency(&self) -> f32 {
+        self.freq
+    }
+
+    /// Set the frequency of the center voice in hertz, keeping the detune.
+    pub fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq;
+        for (voice, &position) in self.voices.iter_mut().zip(self.positions.iter()) {
+            voice.set_frequency(freq * 2.0f32.powf(position * self.detune / 1200.0));
+        }
+    }
+
+    /// Reset every voice, starting them at random phases if enabled.
+    pub fn reset(&mut self) {
+        let mut rng = Rng::new(self.seed);
+        for voice in self.voices.iter_mut() {
+            voice.reset();
+            if self.random_phase {
+                voice.set_phase(rng.next_f32());
+            }
+        }
+    }
+
+    /// Generate the next stereo frame as `[left, right]`.
+    pub fn next_frame(&mut self) -> [f32; 2] {
+        self.voices.iter_mut().zip(self.gains.iter()).fold(
+            [0.0; 2],
+            |[left, right], (voice, gain)| {
+                let out = voice.next_sample();
+                [left + out * gain[0], right + out * gain[1]]
+            },
+        )
+    }
+
+    /// Fill a buffer of interleaved stereo samples with consecutive frames.
+    pub fn process_block(&mut self, block: &mut [f32]) {
+        for frame in block.chunks_exact_mut(2) {
+            let [left, right] = self.next_frame();
+            frame[0] = left;
+            frame[1] = right;
+        }
+    }
+
+    /// Recalculate the equal power pan of each voice, compensating for the
+    /// number of voices so the loudness stays roughly constant.
+    fn update_gains(&mut self) {
+        let compensation = (self.voices.len() as f32).sqrt().recip();
+        for (gain, &position) in self.gains.iter_mut().zip(self.positions.iter()) {
+            let angle = (position * self.stereo_spread + 1.0) * core::f32::consts::FRAC_PI_4;
+            // Keep the center level of mono voices at unity in each channel
+            let scale = core::f32::consts::SQRT_2 * compensation;
+            *gain = [angle.cos() * scale, angle.sin() * scale];
+        }
+    }
+}
+
+impl Unison<SawOscPolyBlep> {
+    /// Classic supersaw of 7 PolyBLEP saws detuned by 25 cents and spread
+    /// across the stereo field.
+    pub fn supersaw(freq: f32, sample_rate: f32) -> Self {
+        let mut unison = Self::new(SawOscPolyBlep::new(freq, sample_rate), 7);
+        unison.set_detune(25.0);
+        unison.set_stereo_spread(1.0);
+        unison
+    }
+}
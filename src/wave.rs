@@ -1,4 +1,92 @@
-use std::{convert::TryFrom, error::Error, io::Write};
+use crate::Error;
+use std::{
+    convert::TryFrom,
+    io::{Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+};
+
+/// Format tag of the extensible `fmt ` chunk.
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Subformat GUID `xxxxxxxx-0000-0010-8000-00aa00389b71`, where the first two
+/// bytes are replaced with the format tag of the data.
+const SUBFORMAT_GUID: [u8; 16] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Speaker layouts which can be written to the channel mask of an extensible
+/// WAVE file. Samples are interleaved in the order listed for each layout.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChannelLayout {
+    /// Front center.
+    Mono,
+    /// Front left, front right.
+    Stereo,
+    /// Front left, front right, back left, back right.
+    Quad,
+    /// Front left, front right, front center, low frequency, back left, back
+    /// right.
+    Surround51,
+    /// Front left, front right, front center, low frequency, back left, back
+    /// right, side left, side right.
+    Surround71,
+}
+
+impl ChannelLayout {
+    /// Number of channels in the layout.
+    pub fn num_channels(self) -> u16 {
+        match self {
+            Self::Mono => 1,
+            Self::Stereo => 2,
+            Self::Quad => 4,
+            Self::Surround51 => 6,
+            Self::Surround71 => 8,
+        }
+    }
+
+    /// Speaker position bits written to `dwChannelMask`.
+    pub fn channel_mask(self) -> u32 {
+        match self {
+            Self::Mono => 0x4,
+            Self::Stereo => 0x3,
+            Self::Quad => 0x33,
+            Self::Surround51 => 0x3F,
+            Self::Surround71 => 0x63F,
+        }
+    }
+
+    /// Default layout for a number of channels.
+    pub fn from_num_channels(num_channels: u16) -> Option<Self> {
+        match num_channels {
+            1 => Some(Self::Mono),
+            2 => Some(Self::Stereo),
+            4 => Some(Self::Quad),
+            6 => Some(Self::Surround51),
+            8 => Some(Self::Surround71),
+            _ => None,
+        }
+    }
+
+    /// Layout matching a `dwChannelMask` value exactly.
+    pub fn from_channel_mask(channel_mask: u32) -> Option<Self> {
+        match channel_mask {
+            0x4 => Some(Self::Mono),
+            0x3 => Some(Self::Stereo),
+            0x33 => Some(Self::Quad),
+            0x3F => Some(Self::Surround51),
+            0x63F => Some(Self::Surround71),
+            _ => None,
+        }
+    }
+}
+
+/// Fields of the extensible `fmt ` chunk following the extension size.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Extensible {
+    valid_bits_per_sample: u16,
+    channel_mask: u32,
+    sub_format: [u8; 16],
+}
 
 /// Header struct for writing the data to a WAVE file.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -16,6 +104,12 @@ pub struct MinimalWaveHeader {
     byte_rate: u32,
     block_align: u16,
     bits_per_sample: u16,
+    // extension size, only present for non-PCM formats
+    cb_size: Option<u16>,
+    // WAVE_FORMAT_EXTENSIBLE fields, present when the extension size is 22
+    extensible: Option<Extensible>,
+    // "fact" chunk sample length, only present for non-PCM formats
+    fact_sample_length: Option<u32>,
     // sub chunk 2 "data"
     sub_chunk_2_id: [u8; 4],
     sub_chunk_2_size: u32,
@@ -47,39 +141,160 @@ impl MinimalWaveHeader {
             byte_rate,
             block_align,
             bits_per_sample,
+            cb_size: None,
+            extensible: None,
+            fact_sample_length: None,
             sub_chunk_2_id: [b'd', b'a', b't', b'a'],
             sub_chunk_2_size,
         }
     }
 
+    /// Create a PCM header for `data_size` bytes of sample data, checking that
+    /// the derived sizes and rates fit in their header fields. The RIFF chunk
+    /// size counts the pad byte following a data chunk of odd length.
+    fn pcm(
+        num_channels: u16,
+        sample_rate: u32,
+        bits_per_sample: u16,
+        data_size: u32,
+    ) -> Result<Self, Error> {
+        if num_channels == 0 {
+            return Err(Error::UnsupportedFormat("Number of channels is zero"));
+        }
+        let block_align = u32::from(num_channels) * u32::from(bits_per_sample) / 8;
+        let byte_rate = sample_rate
+            .checked_mul(block_align)
+            .ok_or(Error::SizeOverflow)?;
+
+        Ok(Self::new(
+            data_size
+                .checked_add(36 + data_size % 2)
+                .ok_or(Error::SizeOverflow)?,
+            16,
+            1,
+            num_channels,
+            sample_rate,
+            byte_rate,
+            u16::try_from(block_align)?,
+            bits_per_sample,
+            data_size,
+        ))
+    }
+
+    /// Create a header for `num_samples` samples of the component type `T`.
+    fn for_component<T: crate::WaveComponent>(
+        num_channels: u16,
+        channel_mask: Option<u32>,
+        sample_rate: u32,
+        num_samples: u32,
+    ) -> Result<Self, Error> {
+        let data_size = (core::mem::size_of::<T>() as u32)
+            .checked_mul(num_samples)
+            .ok_or(Error::SizeOverflow)?;
+
+        Self::for_format(
+            T::AUDIO_FORMAT,
+            core::mem::size_of::<T>() as u16 * 8,
+            num_channels,
+            channel_mask,
+            sample_rate,
+            data_size,
+        )
+    }
+
+    /// Create a header for `data_size` bytes of data with the format tag
+    /// `audio_format`. Non-PCM formats get a `fact` chunk. The 40 byte
+    /// extensible `fmt ` chunk is used for more than 2 channels, more than 16
+    /// bits per sample, or when a channel mask is supplied; other non-PCM
+    /// formats get an 18 byte chunk.
+    fn for_format(
+        audio_format: u16,
+        bits_per_sample: u16,
+        num_channels: u16,
+        channel_mask: Option<u32>,
+        sample_rate: u32,
+        data_size: u32,
+    ) -> Result<Self, Error> {
+        let mut header = Self::pcm(num_channels, sample_rate, bits_per_sample, data_size)?;
+        let mut extra_size = 0u32;
+        if channel_mask.is_some() || num_channels > 2 || bits_per_sample > 16 {
+            let channel_mask = channel_mask.unwrap_or_else(|| {
+                ChannelLayout::from_num_channels(num_channels)
+                    .map_or(0, ChannelLayout::channel_mask)
+            });
+            let [tag_0, tag_1] = audio_format.to_le_bytes();
+            let mut sub_format = SUBFORMAT_GUID;
+            sub_format[0] = tag_0;
+            sub_format[1] = tag_1;
+
+            header.audio_format = WAVE_FORMAT_EXTENSIBLE;
+            header.sub_chunk_1_size = 40;
+            header.cb_size = Some(22);
+            header.extensible = Some(Extensible {
+                valid_bits_per_sample: bits_per_sample,
+                channel_mask,
+                sub_format,
+            });
+            extra_size += 24;
+        } else if audio_format != 1 {
+            header.audio_format = audio_format;
+            header.sub_chunk_1_size = 18;
+            header.cb_size = Some(0);
+            extra_size += 2;
+        }
+        if audio_format != 1 {
+            header.fact_sample_length = Some(data_size / u32::from(header.block_align));
+            extra_size += 12;
+        }
+        header.chunk_size = header
+            .chunk_size
+            .checked_add(extra_size)
+            .ok_or(Error::SizeOverflow)?;
+
+        Ok(header)
+    }
+
     /// Write a WAVE file which is generic over the bit-depth of the output
     /// data size. The user can supply a buffer of `f32` samples ranging from
-    /// -1.0 to 1.0.
+    /// -1.0 to 1.0. Use `f32` or `f64` as the component type for IEEE float
+    /// output and `I24` for 24-bit PCM.
     pub fn write_wave_file<W: Write, T: crate::WaveComponent>(
         w: &mut W,
         data: &[f32],
         num_samples: u32,
         num_channels: u16,
         sample_rate: u32,
-    ) -> Result<(), Box<dyn Error>> {
-        if data.len() != usize::try_from(num_samples)? {
-            return Err("Data length not equal to the number of samples".into());
-        }
+    ) -> Result<(), Error> {
+        check_length(data.len(), usize::try_from(num_samples)?)?;
 
-        let bits_per_sample = core::mem::size_of::<T>() as u16 * 8;
-        let data_size = core::mem::size_of::<T>() as u32 * num_samples;
+        let header = Self::for_component::<T>(num_channels, None, sample_rate, num_samples)?;
+        header.write_wave_header(w)?;
 
-        let header = Self::new(
-            data_size + 36,
-            16,
-            1,
-            num_channels,
+        let data = data
+            .iter()
+            .flat_map(|&d| T::float_to_audio_sample(d))
+            .collect::<Vec<u8>>();
+
+        write_data(w, &data)
+    }
+
+    /// Write a WAVE file like `write_wave_file`, using the extensible format to
+    /// record the speaker positions of the channels in `layout`.
+    pub fn write_wave_file_with_layout<W: Write, T: crate::WaveComponent>(
+        w: &mut W,
+        data: &[f32],
+        num_samples: u32,
+        layout: ChannelLayout,
+        sample_rate: u32,
+    ) -> Result<(), Error> {
+        check_length(data.len(), usize::try_from(num_samples)?)?;
+
+        let header = Self::for_component::<T>(
+            layout.num_channels(),
+            Some(layout.channel_mask()),
             sample_rate,
-            sample_rate * u32::from(num_channels) * u32::from(bits_per_sample) / 8,
-            num_channels * bits_per_sample / 8,
-            bits_per_sample,
-            data_size,
-        );
+            num_samples,
+        )?;
         header.write_wave_header(w)?;
 
         let data = data
@@ -87,10 +302,12 @@ impl MinimalWaveHeader {
             .flat_map(|&d| T::float_to_audio_sample(d))
             .collect::<Vec<u8>>();
 
-        Ok(w.write_all(&data)?)
+        write_data(w, &data)
     }
 
-    /// Write a WAVE file with data already existing in a `u8` buffer.
+    /// Write a WAVE file of PCM data already existing in a `u8` buffer. Like
+    /// `write_wave_file`, the extensible format is used for more than 2
+    /// channels or more than 16 bits per sample.
     pub fn write_wave_file_u8<W: Write>(
         w: &mut W,
         data: &[u8],
@@ -98,29 +315,24 @@ impl MinimalWaveHeader {
         num_channels: u16,
         sample_rate: u32,
         bits_per_sample: u16,
-    ) -> Result<(), Box<dyn Error>> {
-        if data.len() != usize::try_from(data_size)? {
-            return Err("Data length not equal to the number of samples".into());
-        }
+    ) -> Result<(), Error> {
+        check_length(data.len(), usize::try_from(data_size)?)?;
 
-        let header = Self::new(
-            data_size + 36,
-            16,
+        let header = Self::for_format(
             1,
+            bits_per_sample,
             num_channels,
+            None,
             sample_rate,
-            sample_rate * u32::from(num_channels) * u32::from(bits_per_sample) / 8,
-            num_channels * bits_per_sample / 8,
-            bits_per_sample,
             data_size,
-        );
+        )?;
         header.write_wave_header(w)?;
 
-        Ok(w.write_all(data)?)
+        write_data(w, data)
     }
 
     /// Write the header file's contents to a provided writer.
-    pub fn write_wave_header<W: Write>(&self, w: &mut W) -> Result<(), Box<dyn Error>> {
+    pub fn write_wave_header<W: Write>(&self, w: &mut W) -> Result<(), Error> {
         w.write_all(&self.chunk_id)?;
         w.write_all(&self.chunk_size.to_le_bytes())?;
         w.write_all(&self.format)?;
@@ -132,8 +344,721 @@ impl MinimalWaveHeader {
         w.write_all(&self.byte_rate.to_le_bytes())?;
         w.write_all(&self.block_align.to_le_bytes())?;
         w.write_all(&self.bits_per_sample.to_le_bytes())?;
+        if let Some(cb_size) = self.cb_size {
+            w.write_all(&cb_size.to_le_bytes())?;
+        }
+        if let Some(extensible) = &self.extensible {
+            w.write_all(&extensible.valid_bits_per_sample.to_le_bytes())?;
+            w.write_all(&extensible.channel_mask.to_le_bytes())?;
+            w.write_all(&extensible.sub_format)?;
+        }
+        if let Some(sample_length) = self.fact_sample_length {
+            w.write_all(b"fact")?;
+            w.write_all(&4u32.to_le_bytes())?;
+            w.write_all(&sample_length.to_le_bytes())?;
+        }
         w.write_all(&self.sub_chunk_2_id)?;
 
         Ok(w.write_all(&self.sub_chunk_2_size.to_le_bytes())?)
     }
 }
+
+/// Streaming WAVE writer which accepts samples incrementally. A placeholder
+/// header is written on creation and the chunk sizes are patched in when the
+/// writer is finalized or dropped. If a write fails, the amount of data which
+/// reached the writer is unknown, so every later write fails and the header is
+/// left unpatched.
+pub struct WaveWriter<W: Write + Seek, T: crate::WaveComponent> {
+    w: W,
+    start: u64,
+    num_channels: u16,
+    channel_mask: Option<u32>,
+    sample_rate: u32,
+    num_samples: u32,
+    finalized: bool,
+    poisoned: bool,
+    component: PhantomData<T>,
+}
+
+impl<W: Write + Seek, T: crate::WaveComponent> WaveWriter<W, T> {
+    /// Create a new writer and write a placeholder header at the writer's
+    /// current position.
+    pub fn new(w: W, num_channels: u16, sample_rate: u32) -> Result<Self, Error> {
+        Self::with_channel_mask(w, num_channels, None, sample_rate)
+    }
+
+    /// Create a new writer which records the speaker positions of the
+    /// channels in `layout` using the extensible format.
+    pub fn with_layout(w: W, layout: ChannelLayout, sample_rate: u32) -> Result<Self, Error> {
+        Self::with_channel_mask(
+            w,
+            layout.num_channels(),
+            Some(layout.channel_mask()),
+            sample_rate,
+        )
+    }
+
+    fn with_channel_mask(
+        mut w: W,
+        num_channels: u16,
+        channel_mask: Option<u32>,
+        sample_rate: u32,
+    ) -> Result<Self, Error> {
+        let start = w.stream_position()?;
+        MinimalWaveHeader::for_component::<T>(num_channels, channel_mask, sample_rate, 0)?
+            .write_wave_header(&mut w)?;
+
+        Ok(Self {
+            w,
+            start,
+            num_channels,
+            channel_mask,
+            sample_rate,
+            num_samples: 0,
+            finalized: false,
+            poisoned: false,
+            component: PhantomData,
+        })
+    }
+
+    /// Write a single sample ranging from -1.0 to 1.0. Samples are interleaved
+    /// in the order they are written.
+    pub fn write_sample(&mut self, sample: f32) -> Result<(), Error> {
+        self.write_samples(core::slice::from_ref(&sample))
+    }
+
+    /// Write a buffer of interleaved samples ranging from -1.0 to 1.0.
+    pub fn write_samples(&mut self, samples: &[f32]) -> Result<(), Error> {
+        self.check_poisoned()?;
+        let num_samples = self
+            .num_samples
+            .checked_add(u32::try_from(samples.len())?)
+            .ok_or(Error::SizeOverflow)?;
+        // Check the header can describe the new length before writing
+        self.header(num_samples)?;
+
+        let data = samples
+            .iter()
+            .flat_map(|&d| T::float_to_audio_sample(d))
+            .collect::<Vec<u8>>();
+        if let Err(e) = self.w.write_all(&data) {
+            self.poisoned = true;
+            return Err(e.into());
+        }
+        self.num_samples = num_samples;
+
+        Ok(())
+    }
+
+    /// Write one sample frame containing a sample for every channel.
+    pub fn write_frame(&mut self, frame: &[f32]) -> Result<(), Error> {
+        check_length(frame.len(), usize::from(self.num_channels))?;
+        self.write_samples(frame)
+    }
+
+    /// Number of samples written so far across all channels.
+    pub fn num_samples(&self) -> u32 {
+        self.num_samples
+    }
+
+    /// Pad the data chunk to an even length, patch the header with the final
+    /// chunk sizes, and flush the writer. If the samples written don't fill a
+    /// whole number of frames, the last frame is padded with silence so the
+    /// file stays readable and `Error::LengthMismatch` is returned.
+    pub fn finalize(mut self) -> Result<(), Error> {
+        self.finalized = true;
+        self.check_poisoned()?;
+        let num_samples = self.num_samples as usize;
+        self.update_header()?;
+        check_length(num_samples, self.num_samples as usize)
+    }
+
+    /// Fail if an earlier write failed.
+    fn check_poisoned(&self) -> Result<(), Error> {
+        if self.poisoned {
+            Err(Error::Poisoned)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Header describing `num_samples` samples in this writer's format.
+    fn header(&self, num_samples: u32) -> Result<MinimalWaveHeader, Error> {
+        MinimalWaveHeader::for_component::<T>(
+            self.num_channels,
+            self.channel_mask,
+            self.sample_rate,
+            num_samples,
+        )
+    }
+
+    /// Complete the last frame with silence, write the pad byte of an odd
+    /// length data chunk, and rewrite the header with the current data size,
+    /// then return to the end of the file.
+    fn update_header(&mut self) -> Result<(), Error> {
+        let partial = self.num_samples % u32::from(self.num_channels);
+        if partial != 0 {
+            let silence = vec![0.0; usize::from(self.num_channels) - partial as usize];
+            self.write_samples(&silence)?;
+        }
+
+        let header = self.header(self.num_samples)?;
+        if header.sub_chunk_2_size % 2 == 1 {
+            self.w.write_all(&[0])?;
+        }
+        let end = self.w.stream_position()?;
+
+        self.w.seek(SeekFrom::Start(self.start))?;
+        header.write_wave_header(&mut self.w)?;
+        self.w.seek(SeekFrom::Start(end))?;
+
+        Ok(self.w.flush()?)
+    }
+}
+
+impl<W: Write + Seek, T: crate::WaveComponent> Drop for WaveWriter<W, T> {
+    fn drop(&mut self) {
+        if !self.finalized && !self.poisoned {
+            // Errors can't be reported from drop, call `finalize` to handle them
+            let _ = self.update_header();
+        }
+    }
+}
+
+/// Samples and format information decoded from a WAVE file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WaveData {
+    /// Sample rate in hertz.
+    pub sample_rate: u32,
+    /// Number of interleaved channels.
+    pub num_channels: u16,
+    /// Bit depth of the samples stored in the file.
+    pub bits_per_sample: u16,
+    /// Number of significant bits in each sample, which may be less than the
+    /// container size for extensible files.
+    pub valid_bits_per_sample: u16,
+    /// Speaker positions of the channels, or 0 if the file doesn't specify
+    /// them.
+    pub channel_mask: u32,
+    /// Interleaved samples normalized to the range -1.0 to 1.0.
+    pub samples: Vec<f32>,
+}
+
+impl WaveData {
+    /// Number of sample frames, i.e. samples per channel.
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / usize::from(self.num_channels)
+    }
+
+    /// Speaker layout described by the channel mask, if it is a known layout.
+    pub fn channel_layout(&self) -> Option<ChannelLayout> {
+        ChannelLayout::from_channel_mask(self.channel_mask)
+    }
+}
+
+/// Format information parsed from the `fmt ` chunk.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct WaveFormat {
+    audio_format: u16,
+    num_channels: u16,
+    sample_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+    valid_bits_per_sample: u16,
+    channel_mask: u32,
+}
+
+impl MinimalWaveHeader {
+    /// Read a WAVE file, decoding 8, 16, 24, or 32-bit PCM data or 32 or 64-bit
+    /// IEEE float data to `f32` samples ranging from -1.0 to 1.0. Both the basic
+    /// and extensible `fmt ` chunks are supported, and chunks other than `fmt `
+    /// and `data` are skipped.
+    pub fn read_wave_file<R: Read>(r: &mut R) -> Result<WaveData, Error> {
+        let mut id = [0u8; 4];
+        r.read_exact(&mut id)?;
+        if &id != b"RIFF" {
+            return Err(Error::MalformedChunk("Missing RIFF chunk id"));
+        }
+        let _chunk_size = read_u32(r)?;
+        r.read_exact(&mut id)?;
+        if &id != b"WAVE" {
+            return Err(Error::UnsupportedFormat("RIFF form type is not WAVE"));
+        }
+
+        let mut format = None;
+        let mut data = None;
+        while format.is_none() || data.is_none() {
+            // A truncated file may end without a final chunk header
+            if !read_chunk_id(r, &mut id)? {
+                break;
+            }
+            let size = read_u32(r)?;
+            match &id {
+                b"fmt " => format = Some(read_format_chunk(r, size)?),
+                b"data" => {
+                    // Let the buffer grow as data arrives rather than trusting
+                    // the declared size, which may be far larger than the file
+                    let mut buf = Vec::new();
+                    r.by_ref().take(u64::from(size)).read_to_end(&mut buf)?;
+                    if buf.len() != usize::try_from(size)? {
+                        return Err(Error::MalformedChunk(
+                            "Data chunk is shorter than its declared size",
+                        ));
+                    }
+                    data = Some(buf);
+                }
+                _ => skip(r, u64::from(size))?,
+            }
+            // Chunks are padded to an even number of bytes
+            if size % 2 == 1 && (format.is_none() || data.is_none()) {
+                skip(r, 1)?;
+            }
+        }
+
+        let format = format.ok_or(Error::MalformedChunk("Missing fmt chunk"))?;
+        let data = data.ok_or(Error::MalformedChunk("Missing data chunk"))?;
+        if data.len() % usize::from(format.block_align) != 0 {
+            return Err(Error::MalformedChunk(
+                "Data chunk is not a whole number of sample frames",
+            ));
+        }
+
+        Ok(WaveData {
+            sample_rate: format.sample_rate,
+            num_channels: format.num_channels,
+            bits_per_sample: format.bits_per_sample,
+            valid_bits_per_sample: format.valid_bits_per_sample,
+            channel_mask: format.channel_mask,
+            samples: decode_samples(&data, format.audio_format, format.bits_per_sample),
+        })
+    }
+}
+
+/// Parse and validate the contents of a `fmt ` chunk.
+fn read_format_chunk<R: Read>(r: &mut R, size: u32) -> Result<WaveFormat, Error> {
+    if size < 16 {
+        return Err(Error::MalformedChunk("fmt chunk is smaller than 16 bytes"));
+    }
+    let mut audio_format = read_u16(r)?;
+    let num_channels = read_u16(r)?;
+    let sample_rate = read_u32(r)?;
+    let byte_rate = read_u32(r)?;
+    let block_align = read_u16(r)?;
+    let bits_per_sample = read_u16(r)?;
+    let mut valid_bits_per_sample = bits_per_sample;
+    let mut channel_mask = 0;
+
+    if audio_format == WAVE_FORMAT_EXTENSIBLE {
+        if size < 40 || read_u16(r)? < 22 {
+            return Err(Error::MalformedChunk(
+                "Extensible fmt chunk is smaller than 40 bytes",
+            ));
+        }
+        valid_bits_per_sample = read_u16(r)?;
+        channel_mask = read_u32(r)?;
+        let mut sub_format = [0u8; 16];
+        r.read_exact(&mut sub_format)?;
+        skip(r, u64::from(size - 40))?;
+
+        // The first two bytes of the subformat GUID hold the format tag
+        if sub_format[2..] != SUBFORMAT_GUID[2..] {
+            return Err(Error::UnsupportedFormat(
+                "Extensible subformat is not PCM or IEEE float",
+            ));
+        }
+        audio_format = u16::from_le_bytes([sub_format[0], sub_format[1]]);
+        if valid_bits_per_sample == 0 || valid_bits_per_sample > bits_per_sample {
+            return Err(Error::MalformedChunk(
+                "Valid bits per sample exceeds bits per sample",
+            ));
+        }
+        if channel_mask.count_ones() > u32::from(num_channels) {
+            return Err(Error::MalformedChunk(
+                "Channel mask has more speakers than channels",
+            ));
+        }
+    } else {
+        skip(r, u64::from(size - 16))?;
+    }
+
+    match (audio_format, bits_per_sample) {
+        (1, 8) | (1, 16) | (1, 24) | (1, 32) | (3, 32) | (3, 64) => {}
+        (1, _) | (3, _) => {
+            return Err(Error::UnsupportedFormat(
+                "Bits per sample is not supported for this format",
+            ))
+        }
+        _ => {
+            return Err(Error::UnsupportedFormat(
+                "Format tag is not PCM, IEEE float, or extensible",
+            ))
+        }
+    }
+    if num_channels == 0 {
+        return Err(Error::UnsupportedFormat("Number of channels is zero"));
+    }
+    if sample_rate == 0 {
+        return Err(Error::MalformedChunk("Sample rate is zero"));
+    }
+    if u32::from(block_align) != u32::from(num_channels) * u32::from(bits_per_sample) / 8 {
+        return Err(Error::MalformedChunk(
+            "Block align does not match channels and bits per sample",
+        ));
+    }
+    if u64::from(byte_rate) != u64::from(sample_rate) * u64::from(block_align) {
+        return Err(Error::MalformedChunk(
+            "Byte rate does not match sample rate and block align",
+        ));
+    }
+
+    Ok(WaveFormat {
+        audio_format,
+        num_channels,
+        sample_rate,
+        block_align,
+        bits_per_sample,
+        valid_bits_per_sample,
+        channel_mask,
+    })
+}
+
+/// Convert raw little-endian sample data to normalized `f32` samples, the
+/// inverse of `WaveComponent::float_to_audio_sample`.
+fn decode_samples(data: &[u8], audio_format: u16, bits_per_sample: u16) -> Vec<f32> {
+    match (audio_format, bits_per_sample) {
+        (1, 8) => data.iter().map(|&b| f32::from(b) / 127.5 - 1.0).collect(),
+        (1, 16) => data
+            .chunks_exact(2)
+            .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / f32::from(i16::MAX))
+            .collect(),
+        (1, 24) => data
+            .chunks_exact(3)
+            // Place the bytes in the upper 24 bits and shift to sign-extend
+            .map(|b| (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_607.0)
+            .collect(),
+        (1, 32) => data
+            .chunks_exact(4)
+            .map(|b| {
+                (f64::from(i32::from_le_bytes([b[0], b[1], b[2], b[3]])) / f64::from(i32::MAX))
+                    as f32
+            })
+            .collect(),
+        (3, 32) => data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        (3, 64) => data
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as f32)
+            .collect(),
+        _ => unreachable!("format is validated when reading the fmt chunk"),
+    }
+}
+
+/// Read a chunk id, returning `false` if the reader is already at its end.
+fn read_chunk_id<R: Read>(r: &mut R, id: &mut [u8; 4]) -> Result<bool, Error> {
+    let mut read = 0;
+    while read < id.len() {
+        match r.read(&mut id[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(Error::MalformedChunk("Truncated chunk header")),
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(Error::Io(e)),
+        }
+    }
+    Ok(true)
+}
+
+fn read_u16<R: Read>(r: &mut R) -> Result<u16, Error> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32<R: Read>(r: &mut R) -> Result<u32, Error> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+/// Discard `n` bytes from the reader.
+fn skip<R: Read>(r: &mut R, n: u64) -> Result<(), Error> {
+    if std::io::copy(&mut r.by_ref().take(n), &mut std::io::sink())? != n {
+        return Err(Error::MalformedChunk(
+            "Unexpected end of file while skipping chunk",
+        ));
+    }
+    Ok(())
+}
+
+/// Write the contents of the data chunk, followed by a pad byte if its length
+/// is odd.
+fn write_data<W: Write>(w: &mut W, data: &[u8]) -> Result<(), Error> {
+    w.write_all(data)?;
+    if data.len() % 2 == 1 {
+        w.write_all(&[0])?;
+    }
+    Ok(())
+}
+
+/// Check that a buffer has the expected number of elements.
+fn check_length(actual: usize, expected: usize) -> Result<(), Error> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(Error::LengthMismatch { expected, actual })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WaveComponent, I24};
+    use std::io::Cursor;
+
+    const SAMPLE_RATE: u32 = 8000;
+
+    fn test_samples(len: usize) -> Vec<f32> {
+        (0..len).map(|i| (i as f32 * 0.37).sin() * 0.9).collect()
+    }
+
+    fn riff_size(file: &[u8]) -> u32 {
+        u32::from_le_bytes([file[4], file[5], file[6], file[7]])
+    }
+
+    fn audio_format(file: &[u8]) -> u16 {
+        u16::from_le_bytes([file[20], file[21]])
+    }
+
+    fn has_chunk(file: &[u8], id: &[u8; 4]) -> bool {
+        file.windows(4).any(|window| window == id)
+    }
+
+    /// Write samples with the component type `T`, read them back, and check
+    /// they match within `tolerance`.
+    fn round_trip<T: WaveComponent>(num_channels: u16, tolerance: f32) -> (Vec<u8>, WaveData) {
+        let samples = test_samples(usize::from(num_channels) * 9);
+        let mut file = Vec::new();
+        MinimalWaveHeader::write_wave_file::<_, T>(
+            &mut file,
+            &samples,
+            samples.len() as u32,
+            num_channels,
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        assert_eq!(riff_size(&file) as usize, file.len() - 8);
+
+        let data = MinimalWaveHeader::read_wave_file(&mut file.as_slice()).unwrap();
+        assert_eq!(data.sample_rate, SAMPLE_RATE);
+        assert_eq!(data.num_channels, num_channels);
+        assert_eq!(data.bits_per_sample as usize, core::mem::size_of::<T>() * 8);
+        assert_eq!(data.samples.len(), samples.len());
+        for (read, written) in data.samples.iter().zip(samples.iter()) {
+            assert!(
+                (read - written).abs() <= tolerance,
+                "{} != {}",
+                read,
+                written
+            );
+        }
+        (file, data)
+    }
+
+    #[test]
+    fn round_trip_u8() {
+        let (file, _) = round_trip::<u8>(1, 1.0 / 127.0);
+        assert_eq!(audio_format(&file), 1);
+        assert!(!has_chunk(&file, b"fact"));
+    }
+
+    #[test]
+    fn round_trip_i16() {
+        let (file, _) = round_trip::<i16>(2, 1.0 / 32767.0);
+        assert_eq!(audio_format(&file), 1);
+        assert_eq!(file.len(), 44 + 2 * 2 * 9);
+    }
+
+    #[test]
+    fn round_trip_i24() {
+        let (file, data) = round_trip::<I24>(1, 1.0 / 8_388_607.0);
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+        assert_eq!(data.valid_bits_per_sample, 24);
+    }
+
+    #[test]
+    fn round_trip_i32() {
+        let (file, _) = round_trip::<i32>(2, 1e-6);
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+    }
+
+    #[test]
+    fn round_trip_f32() {
+        let (file, _) = round_trip::<f32>(2, 0.0);
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+        assert!(has_chunk(&file, b"fact"));
+    }
+
+    #[test]
+    fn round_trip_f64() {
+        let (file, _) = round_trip::<f64>(1, 0.0);
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+        assert!(has_chunk(&file, b"fact"));
+    }
+
+    #[test]
+    fn round_trip_extensible_layout() {
+        let samples = test_samples(6 * 5);
+        let mut file = Vec::new();
+        MinimalWaveHeader::write_wave_file_with_layout::<_, f32>(
+            &mut file,
+            &samples,
+            samples.len() as u32,
+            ChannelLayout::Surround51,
+            SAMPLE_RATE,
+        )
+        .unwrap();
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+        assert!(has_chunk(&file, b"fact"));
+
+        let data = MinimalWaveHeader::read_wave_file(&mut file.as_slice()).unwrap();
+        assert_eq!(data.channel_layout(), Some(ChannelLayout::Surround51));
+        assert_eq!(data.num_frames(), 5);
+        assert_eq!(data.samples, samples);
+    }
+
+    #[test]
+    fn wave_file_u8_uses_extensible_for_many_channels() {
+        let data = [0x00, 0x80, 0xFF, 0x7F, 0x01, 0x80, 0xFE, 0x7F];
+        let mut file = Vec::new();
+        MinimalWaveHeader::write_wave_file_u8(&mut file, &data, 8, 4, SAMPLE_RATE, 16).unwrap();
+        assert_eq!(audio_format(&file), WAVE_FORMAT_EXTENSIBLE);
+
+        let read = MinimalWaveHeader::read_wave_file(&mut file.as_slice()).unwrap();
+        assert_eq!(read.channel_layout(), Some(ChannelLayout::Quad));
+        assert_eq!(read.num_frames(), 1);
+    }
+
+    #[test]
+    fn odd_length_data_is_padded() {
+        let samples = test_samples(3);
+        let mut file = Vec::new();
+        MinimalWaveHeader::write_wave_file::<_, u8>(&mut file, &samples, 3, 1, SAMPLE_RATE)
+            .unwrap();
+        assert_eq!(file.len(), 44 + 3 + 1);
+        assert_eq!(riff_size(&file) as usize, file.len() - 8);
+        assert_eq!(file.last(), Some(&0));
+
+        // A chunk after the pad byte stays aligned and is skipped
+        let mut with_chunk = file.clone();
+        with_chunk.extend_from_slice(b"LIST");
+        with_chunk.extend_from_slice(&2u32.to_le_bytes());
+        with_chunk.extend_from_slice(&[0, 0]);
+        let data = MinimalWaveHeader::read_wave_file(&mut with_chunk.as_slice()).unwrap();
+        assert_eq!(data.samples.len(), 3);
+    }
+
+    #[test]
+    fn streaming_writer_matches_wave_file() {
+        let samples = test_samples(7);
+        for &num_channels in [1u16, 7].iter() {
+            let mut expected = Vec::new();
+            MinimalWaveHeader::write_wave_file::<_, u8>(
+                &mut expected,
+                &samples,
+                samples.len() as u32,
+                num_channels,
+                SAMPLE_RATE,
+            )
+            .unwrap();
+
+            let mut cursor = Cursor::new(Vec::new());
+            let mut writer =
+                WaveWriter::<_, u8>::new(&mut cursor, num_channels, SAMPLE_RATE).unwrap();
+            for &sample in samples.iter() {
+                writer.write_sample(sample).unwrap();
+            }
+            writer.finalize().unwrap();
+            assert_eq!(cursor.into_inner(), expected);
+        }
+    }
+
+    #[test]
+    fn streaming_writer_completes_partial_frames() {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = WaveWriter::<_, i16>::new(&mut cursor, 2, SAMPLE_RATE).unwrap();
+        writer.write_samples(&[0.5, -0.5, 0.25]).unwrap();
+        assert!(matches!(
+            writer.finalize(),
+            Err(Error::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+        let data = MinimalWaveHeader::read_wave_file(&mut cursor.get_ref().as_slice()).unwrap();
+        assert_eq!(data.num_frames(), 2);
+        assert_eq!(data.samples[3], 0.0);
+
+        // Dropping the writer completes the frame in the same way
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = WaveWriter::<_, u8>::new(&mut cursor, 3, SAMPLE_RATE).unwrap();
+        writer.write_sample(0.5).unwrap();
+        drop(writer);
+        let data = MinimalWaveHeader::read_wave_file(&mut cursor.get_ref().as_slice()).unwrap();
+        assert_eq!(data.num_frames(), 1);
+    }
+
+    #[test]
+    fn streaming_writer_is_poisoned_by_write_errors() {
+        /// Writer which fails once `limit` bytes have been written.
+        struct Limited {
+            inner: Cursor<Vec<u8>>,
+            limit: usize,
+        }
+
+        impl Write for Limited {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(self.limit - self.inner.get_ref().len());
+                if len == 0 {
+                    return Err(std::io::ErrorKind::WriteZero.into());
+                }
+                self.inner.write(&buf[..len])
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Seek for Limited {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        let mut limited = Limited {
+            inner: Cursor::new(Vec::new()),
+            limit: 44 + 3,
+        };
+        let mut writer = WaveWriter::<_, i16>::new(&mut limited, 1, SAMPLE_RATE).unwrap();
+        assert!(matches!(
+            writer.write_samples(&[0.1, 0.2]),
+            Err(Error::Io(_))
+        ));
+        assert!(matches!(writer.write_sample(0.3), Err(Error::Poisoned)));
+        assert!(matches!(writer.finalize(), Err(Error::Poisoned)));
+    }
+
+    #[test]
+    fn oversized_data_chunk_is_an_error() {
+        let mut file = Vec::new();
+        MinimalWaveHeader::write_wave_file::<_, i16>(&mut file, &[], 0, 1, SAMPLE_RATE).unwrap();
+        let len = file.len();
+        file[len - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            MinimalWaveHeader::read_wave_file(&mut file.as_slice()),
+            Err(Error::MalformedChunk(_))
+        ));
+    }
+}
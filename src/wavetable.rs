@@ -0,0 +1,268 @@
+use crate::oscillator::{advance_unit_phase, Oscillator};
+use std::sync::Arc;
+
+/// Method used to read between the samples of a wavetable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Interpolation {
+    /// Straight line between the two nearest samples.
+    Linear,
+    /// Catmull-Rom spline through the four nearest samples.
+    Cubic,
+}
+
+/// Single-cycle waveform stored as a set of band-limited mip levels. Each
+/// level holds half the harmonics of the one before it, so a level can be
+/// chosen which has no harmonics above the Nyquist frequency.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wavetable {
+    size: usize,
+    max_harmonics: usize,
+    // One cycle per level, with one guard sample before and two after
+    levels: Vec<Vec<f32>>,
+}
+
+impl Wavetable {
+    /// Build a wavetable from one cycle of a waveform. The number of samples
+    /// in the cycle is used as the size of every mip level.
+    ///
+    /// Panics if the cycle has fewer than 4 samples.
+    pub fn from_cycle(cycle: &[f32]) -> Self {
+        assert!(cycle.len() >= 4, "cycle must contain at least 4 samples");
+        let size = cycle.len();
+        let (cos, sin) = unit_circle(size);
+        let max_harmonics = size / 2 - 1;
+
+        // Analyze the cycle with a discrete Fourier transform
+        let dc = cycle.iter().map(|&x| f64::from(x)).sum::<f64>() / size as f64;
+        let mut cos_amps = vec![0.0; max_harmonics + 1];
+        let mut sin_amps = vec![0.0; max_harmonics + 1];
+        for h in 1..=max_harmonics {
+            for (j, &x) in cycle.iter().enumerate() {
+                cos_amps[h] += f64::from(x) * cos[h * j % size];
+                sin_amps[h] += f64::from(x) * sin[h * j % size];
+            }
+            cos_amps[h] *= 2.0 / size as f64;
+            sin_amps[h] *= 2.0 / size as f64;
+        }
+
+        Self::from_spectrum(size, dc, &cos_amps, &sin_amps)
+    }
+
+    /// Saw wave table using the harmonic series of `advance_saw_osc_bl`.
+    pub fn saw(size: usize) -> Self {
+        Self::from_sine_series(size, |h| 2.0 / (core::f64::consts::PI * h as f64))
+    }
+
+    /// Square wave table using the harmonic series of `advance_square_osc_bl`.
+    pub fn square(size: usize) -> Self {
+        Self::from_sine_series(size, |h| {
+            if h % 2 == 1 {
+                4.0 / (core::f64::consts::PI * h as f64)
+            } else {
+                0.0
+            }
+        })
+    }
+
+    /// Triangle wave table using the harmonic series of
+    /// `advance_triangle_osc_bl`.
+    pub fn triangle(size: usize) -> Self {
+        Self::from_sine_series(size, |h| {
+            let amp = 8.0 / (core::f64::consts::PI.powi(2) * (h * h) as f64);
+            match h % 4 {
+                1 => -amp,
+                3 => amp,
+                _ => 0.0,
+            }
+        })
+    }
+
+    /// Number of samples in one cycle.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of mip levels.
+    pub fn num_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Build a table from the amplitude of the sine at each harmonic.
+    fn from_sine_series<F: Fn(usize) -> f64>(size: usize, amp: F) -> Self {
+        assert!(size >= 4, "size must be at least 4 samples");
+        let max_harmonics = size / 2 - 1;
+        let cos_amps = vec![0.0; max_harmonics + 1];
+        let sin_amps = (0..=max_harmonics)
+            .map(|h| if h == 0 { 0.0 } else { amp(h) })
+            .collect::<Vec<_>>();
+
+        Self::from_spectrum(size, 0.0, &cos_amps, &sin_amps)
+    }
+
+    /// Synthesize the mip levels from cosine and sine amplitudes indexed by
+    /// harmonic, halving the number of harmonics at each level.
+    fn from_spectrum(size: usize, dc: f64, cos_amps: &[f64], sin_amps: &[f64]) -> Self {
+        let (cos, sin) = unit_circle(size);
+        let max_harmonics = size / 2 - 1;
+
+        let mut levels = Vec::new();
+        let mut harmonics = max_harmonics;
+        while harmonics > 0 {
+            let cycle = (0..size)
+                .map(|j| {
+                    (1..=harmonics).fold(dc, |sum, h| {
+                        sum + cos_amps[h] * cos[h * j % size] + sin_amps[h] * sin[h * j % size]
+                    }) as f32
+                })
+                .collect::<Vec<f32>>();
+
+            let mut level = Vec::with_capacity(size + 3);
+            level.push(cycle[size - 1]);
+            level.extend_from_slice(&cycle);
+            level.extend_from_slice(&cycle[..2]);
+            levels.push(level);
+
+            harmonics /= 2;
+        }
+
+        Self {
+            size,
+            max_harmonics,
+            levels,
+        }
+    }
+
+    /// Read a level at a position ranging from 0.0 to the table size.
+    fn read(&self, level: usize, pos: f32, interpolation: Interpolation) -> f32 {
+        let table = &self.levels[level];
+        let i = (pos as usize).min(self.size - 1);
+        let frac = pos - i as f32;
+
+        match interpolation {
+            Interpolation::Linear => table[i + 1] + (table[i + 2] - table[i + 1]) * frac,
+            Interpolation::Cubic => {
+                hermite(table[i], table[i + 1], table[i + 2], table[i + 3], frac)
+            }
+        }
+    }
+}
+
+/// Cubic Hermite interpolation between `y_0` and `y_1` at `frac` from 0.0 to
+/// 1.0, using the neighboring points for the slopes.
+pub(crate) fn hermite(y_m1: f32, y_0: f32, y_1: f32, y_2: f32, frac: f32) -> f32 {
+    let c1 = 0.5 * (y_1 - y_m1);
+    let c2 = y_m1 - 2.5 * y_0 + 2.0 * y_1 - 0.5 * y_2;
+    let c3 = 0.5 * (y_2 - y_m1) + 1.5 * (y_0 - y_1);
+    ((c3 * frac + c2) * frac + c1) * frac + y_0
+}
+
+/// Cosine and sine of `size` evenly spaced angles around the unit circle.
+fn unit_circle(size: usize) -> (Vec<f64>, Vec<f64>) {
+    (0..size)
+        .map(|j| {
+            let angle = core::f64::consts::TAU * j as f64 / size as f64;
+            (angle.cos(), angle.sin())
+        })
+        .unzip()
+}
+
+/// Wavetable oscillator which crossfades between the two mip levels nearest
+/// the playback frequency that are free of aliasing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WavetableOsc {
+    table: Arc<Wavetable>,
+    interpolation: Interpolation,
+    phase: f32,
+    freq: f32,
+    sample_rate: f32,
+    level: usize,
+    fade: f32,
+}
+
+impl WavetableOsc {
+    /// Create a new oscillator starting at phase 0.
+    pub fn new(
+        table: Arc<Wavetable>,
+        freq: f32,
+        sample_rate: f32,
+        interpolation: Interpolation,
+    ) -> Self {
+        let mut osc = Self {
+            table,
+            interpolation,
+            phase: 0.0,
+            freq,
+            sample_rate,
+            level: 0,
+            fade: 0.0,
+        };
+        osc.select_level();
+        osc
+    }
+
+    /// Replace the wavetable, keeping the current phase.
+    pub fn set_table(&mut self, table: Arc<Wavetable>) {
+        self.table = table;
+        self.select_level();
+    }
+
+    /// Set the interpolation used between table samples.
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    /// Choose the levels to crossfade between for the current frequency.
+    fn select_level(&mut self) {
+        let nyquist = self.sample_rate * 0.5;
+        // Octaves above the highest frequency level 0 can play without
+        // aliasing. Level 0 starts fading into level 1 an octave below that, so
+        // each level is fully faded in by the time the one before it aliases.
+        let octave = (self.table.max_harmonics as f32 * self.freq.abs() / nyquist).log2();
+        if octave > -1.0 {
+            let level = octave.ceil();
+            self.level = level as usize;
+            self.fade = octave + 1.0 - level;
+        } else {
+            self.level = 0;
+            self.fade = 0.0;
+        }
+
+        let last = self.table.num_levels() - 1;
+        if self.level >= last {
+            self.level = last;
+            self.fade = 0.0;
+        }
+    }
+}
+
+impl Oscillator for WavetableOsc {
+    fn next_sample(&mut self) -> f32 {
+        advance_unit_phase(&mut self.phase, self.freq, self.sample_rate);
+        let pos = self.phase * self.table.size as f32;
+
+        let out = self.table.read(self.level, pos, self.interpolation);
+        if self.fade > 0.0 {
+            let next = self.table.read(self.level + 1, pos, self.interpolation);
+            out + (next - out) * self.fade
+        } else {
+            out
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+
+    fn frequency(&self) -> f32 {
+        self.freq
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        self.freq = freq;
+        self.select_level();
+    }
+
+    fn set_phase(&mut self, phase: f32) {
+        self.phase = phase - phase.floor();
+    }
+}